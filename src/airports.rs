@@ -0,0 +1,93 @@
+use crate::bot::Trip;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::LazyLock,
+};
+
+static COUNTRIES: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
+    include_str!("airports.csv")
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|l| l.trim().split_once(','))
+        .collect()
+});
+
+// the country an airport is in, None for airports not in the table
+pub fn country(code: &str) -> Option<&'static str> {
+    COUNTRIES.get(code.trim().to_uppercase().as_str()).copied()
+}
+
+// where home is and the airport groups layover filters can name, from config.json
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Airports {
+    pub home_country: String,
+    // e.g. "Europe": ["LHR", "CDG", "AMS"], names are matched ignoring case
+    pub groups: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for Airports {
+    fn default() -> Self {
+        Airports {
+            home_country: "US".to_owned(),
+            groups: BTreeMap::new(),
+        }
+    }
+}
+
+impl Airports {
+    // a layover filter entry is either a group name or an airport code
+    pub fn matches(&self, entry: &str, layover: &str) -> bool {
+        let entry = entry.trim();
+        match self
+            .groups
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(entry))
+        {
+            Some((_, codes)) => codes.iter().any(|c| c.eq_ignore_ascii_case(layover)),
+            None => entry.eq_ignore_ascii_case(layover),
+        }
+    }
+
+    pub fn any_layover(&self, entries: &[String], trip: &Trip) -> bool {
+        trip.layovers
+            .iter()
+            .any(|l| entries.iter().any(|e| self.matches(e, l)))
+    }
+
+    // a layover in another country, airports missing from the table don't count
+    pub fn is_international(&self, trip: &Trip) -> bool {
+        trip.layovers
+            .iter()
+            .filter_map(|l| country(l))
+            .any(|c| !c.eq_ignore_ascii_case(&self.home_country))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::test_support::parse_trip as trip;
+
+    #[test]
+    fn matches_groups_and_countries() {
+        let airports = Airports {
+            groups: BTreeMap::from([(
+                "West Coast".to_owned(),
+                vec!["LAX".to_owned(), "SFO".to_owned(), "SEA".to_owned()],
+            )]),
+            ..Airports::default()
+        };
+        let west = trip("A1 05JAN 3 0600 0700 1800 1500 1800 SFO LAX");
+        let europe = trip("A2 05JAN 3 0600 0700 1800 1500 1800 BOS DUB");
+
+        assert_eq!(country("dub"), Some("IE"));
+        assert_eq!(country("XXX"), None);
+        assert!(airports.any_layover(&["west coast".to_owned()], &west));
+        assert!(!airports.any_layover(&["West Coast".to_owned(), "LHR".to_owned()], &europe));
+        assert!(airports.any_layover(&["dub".to_owned()], &europe));
+        assert!(!airports.is_international(&west));
+        assert!(airports.is_international(&europe));
+    }
+}
@@ -0,0 +1,58 @@
+use rodio::{Decoder, OutputStream, Sink, source::Source};
+use std::{fmt::Display, fs::File, io::BufReader};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AlarmError {
+    NoOutput(String),
+    Sound(String),
+}
+
+impl Display for AlarmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlarmError::NoOutput(e) => write!(f, "no audio output: {}", e),
+            AlarmError::Sound(e) => write!(f, "could not load alarm sound: {}", e),
+        }
+    }
+}
+
+// how the bot gets the user's attention, it keeps going until paused
+pub trait Alarm {
+    fn play(&mut self);
+    fn pause(&mut self);
+}
+
+// loops a sound file on the default output
+pub struct SoundAlarm {
+    // the sink goes quiet once the stream is dropped
+    _stream: OutputStream,
+    sink: Sink,
+}
+
+impl SoundAlarm {
+    pub fn new(path: &str) -> Result<Self, AlarmError> {
+        let (stream, handle) =
+            OutputStream::try_default().map_err(|e| AlarmError::NoOutput(e.to_string()))?;
+        let file = File::open(path).map_err(|e| AlarmError::Sound(format!("{}: {}", path, e)))?;
+        let source = Decoder::new(BufReader::new(file))
+            .map_err(|e| AlarmError::Sound(format!("{}: {}", path, e)))?;
+        let sink = Sink::try_new(&handle).map_err(|e| AlarmError::NoOutput(e.to_string()))?;
+        sink.append(source.repeat_infinite());
+        sink.pause();
+
+        Ok(SoundAlarm {
+            _stream: stream,
+            sink,
+        })
+    }
+}
+
+impl Alarm for SoundAlarm {
+    fn play(&mut self) {
+        self.sink.play();
+    }
+
+    fn pause(&mut self) {
+        self.sink.pause();
+    }
+}
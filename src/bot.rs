@@ -1,27 +1,32 @@
-use crate::{App, AppState, Message};
-use enigo::{
-    Button, Coordinate,
-    Direction::{Click, Press, Release},
-    Enigo, Key, Keyboard, Mouse, Settings,
+use crate::{
+    App, AppState, Message,
+    airports::{self, Airports},
+    alarm::{Alarm, SoundAlarm},
+    capture::{Capture, CaptureError, LiveCapture, ReplayCapture},
+    clipboard::{Clipboard, ClipboardError, SystemClipboard, copy_text},
+    credit::{CreditBudget, CreditError, CreditTally},
+    input::{EnigoInput, Input, InputEvent, RecordedInput},
+    opentime::{self, ParseDiagnostic, ParsedPage},
+    schedule::Schedule,
+    trace::TripTrace,
 };
-use regex::{Regex, RegexBuilder};
-use rodio::{Decoder, OutputStream, Sink, source::Source};
+use chrono::{Datelike, Local, NaiveDate, TimeDelta};
+use enigo::{InputError, Key};
 use serde::{Deserialize, Serialize};
 use std::{
-    cell::LazyCell,
     fmt::Display,
     fs::File,
-    io::{BufReader, prelude::*},
-    ops::Sub,
+    io::prelude::*,
     str::FromStr,
-    sync::mpsc::{Receiver, Sender},
+    sync::mpsc::{Receiver, Sender, TryRecvError},
     thread,
     time::{Duration, Instant},
 };
 
 #[derive(Debug, Clone)]
 pub enum BotMessage {
-    Start(Vec<Rule>),
+    // the last field is true for a dry run, where pickups are only reported
+    Start(RuleSet, Schedule, bool),
     Stop,
     TripFound,
     Screenshot,
@@ -29,6 +34,21 @@ pub enum BotMessage {
     CopyScreen,
     Waiting(u64),
     Copied(String),
+    CopyFailed(ClipboardError),
+    BadRow(ParseDiagnostic),
+    Evaluated(TripTrace),
+    // a trip a dry run would have picked up and the keystrokes it would have sent
+    DryRunPickup(String, Vec<InputEvent>),
+    // the trip and why its keystrokes could not be sent, the bot stops either way
+    PickupFailed(String, InputError),
+    // the bot stops
+    CaptureFailed(CaptureError),
+    // a click or key press couldn't be sent, the bot stops
+    InputFailed(InputError),
+    // the credit tally couldn't be read or saved, pickups only alert while it can't be read
+    CreditFailed(CreditError),
+    // the last frame of a replay has been checked and the bot stops
+    ReplayFinished,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -36,6 +56,17 @@ pub struct BotConfig {
     pub updated_time_pos: (i32, i32, u32, u32),
     pub refresh_interval: (u32, u32),
     pub refresh: [u32; 4],
+    // monthly credit cap, pickups that would go over it only alert
+    #[serde(default)]
+    pub credit_cap: Option<Time>,
+    // credit already on this month's schedule
+    #[serde(default)]
+    pub existing_credit: Time,
+    #[serde(default)]
+    pub airports: Airports,
+    // a directory of <timestamp>.png frames to play back instead of capturing the screen
+    #[serde(default)]
+    pub replay: Option<String>,
 }
 
 impl BotConfig {
@@ -54,11 +85,23 @@ impl BotConfig {
         serde_json::from_str(&data).or(Err(()))
     }
 
+    // the screen, or the recorded frames when replay is set
+    fn capture(&self) -> Result<Box<dyn Capture>, CaptureError> {
+        Ok(match &self.replay {
+            Some(dir) => Box::new(ReplayCapture::open(dir.as_ref())?),
+            None => Box::new(LiveCapture::new()?),
+        })
+    }
+
     fn save_default() {
         let conf = BotConfig {
             updated_time_pos: (517, 179, 150, 40),
             refresh_interval: (10, 30),
             refresh: [87, 62, 20, 20],
+            credit_cap: None,
+            existing_credit: Time::default(),
+            airports: Airports::default(),
+            replay: None,
         };
 
         let js: String = match serde_json::to_string(&conf) {
@@ -79,25 +122,151 @@ pub struct Rule {
     pub name: String,
     pub filters: Vec<Filter>,
     pub action: BotAction,
+    // only used in MatchMode::Priority, higher wins
+    pub priority: u8,
+    // disabled rules are kept in the file but never match
+    pub enabled: bool,
+    // what the rule is for, shown under the name
+    pub notes: String,
+    // added to the score of every trip the rule matches, whether or not it decides the action
+    pub points: i32,
+    // scales the points by the hours in this field, e.g. points per hour of credit per day
+    pub per_hour: Option<Field>,
 }
 
-impl Rule {
-    pub fn eval(&self, trip: &Trip) -> bool {
-        for filter in &self.filters {
-            if !filter.eval(trip) {
-                println!("trip {} failed filter {:?}", trip.id, filter);
-                return false;
+// an enabled rule that alerts on every trip
+impl Default for Rule {
+    fn default() -> Self {
+        Rule {
+            name: String::new(),
+            filters: vec![],
+            action: BotAction::Alert,
+            priority: 0,
+            enabled: true,
+            notes: String::new(),
+            points: 0,
+            per_hour: None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum MatchMode {
+    // the matching rule with the highest priority wins, ties go to the earlier rule
+    #[default]
+    Priority,
+    // the first matching rule in list order wins
+    FirstMatch,
+}
+
+impl Display for MatchMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                MatchMode::Priority => "Priority",
+                MatchMode::FirstMatch => "First match",
             }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RuleSet {
+    pub mode: MatchMode,
+    // act on the best scoring trips on a page first instead of going down the page
+    pub ranked: bool,
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    // one rule per line after the mode
+    pub fn as_string(&self) -> String {
+        let mode = match self.mode {
+            MatchMode::Priority => "priority",
+            MatchMode::FirstMatch => "first-match",
+        };
+        let mut s = format!(
+            "mode {}{}\n",
+            mode,
+            if self.ranked { " ranked" } else { "" }
+        );
+        for rule in &self.rules {
+            s += &format!("\n{}", rule.as_string());
         }
+        s
+    }
+}
 
-        true
+// what rules are checked against besides the trip itself
+#[derive(Debug, Clone, Default)]
+pub struct EvalContext {
+    pub today: Date,
+    pub schedule: Schedule,
+    pub credit: CreditBudget,
+    pub airports: Airports,
+}
+
+impl Rule {
+    pub fn eval(&self, trip: &Trip, ctx: &EvalContext) -> bool {
+        self.filters.iter().all(|f| f.eval(trip, ctx))
     }
 
-    pub fn get_action(&self, trip: &Trip) -> BotAction {
-        if self.eval(trip) {
-            self.action
+    // what matching this rule adds to a trip's score
+    pub fn points_for(&self, trip: &Trip) -> i32 {
+        match self.per_hour {
+            Some(field) => (self.points as i64 * trip.get(field).minutes() as i64 / 60) as i32,
+            None => self.points,
+        }
+    }
+
+    // path is the index of each filter from the top level down through groups
+    pub fn filter_mut(&mut self, path: &[usize]) -> Option<&mut Filter> {
+        let (first, rest) = path.split_first()?;
+        let mut filter = self.filters.get_mut(*first)?;
+        for i in rest {
+            filter = filter.children_mut()?.get_mut(*i)?;
+        }
+        Some(filter)
+    }
+
+    // alert "late reports" priority 2 when report >= 10:00 and days <= 2
+    pub fn as_string(&self) -> String {
+        let action = match self.action {
+            BotAction::Nothing => "nothing",
+            BotAction::Alert => "alert",
+            BotAction::Pickup => "pickup",
+            BotAction::Ignore => "ignore",
+        };
+        let mut s = format!("{} {}", action, quote(&self.name));
+        if self.priority != 0 {
+            s += &format!(" priority {}", self.priority);
+        }
+        if !self.enabled {
+            s += " disabled";
+        }
+        if !self.notes.is_empty() {
+            s += &format!(" notes {}", quote(&self.notes));
+        }
+        if self.points != 0 {
+            s += &format!(" points {}", self.points);
+        }
+        if let Some(field) = self.per_hour {
+            s += &format!(" per {}", field.keyword());
+        }
+        if !self.filters.is_empty() {
+            s += &format!(" when {}", join_filters(&self.filters, " and "));
+        }
+        s
+    }
+
+    // an empty path refers to the top level filter list
+    pub fn group_mut(&mut self, path: &[usize]) -> Option<&mut Vec<Filter>> {
+        if path.is_empty() {
+            Some(&mut self.filters)
         } else {
-            BotAction::Nothing
+            self.filter_mut(path)?.children_mut()
         }
     }
 }
@@ -112,6 +281,19 @@ pub enum Op {
     Gt,
 }
 
+impl Op {
+    pub fn compare<T: PartialOrd>(self, lhs: T, rhs: T) -> bool {
+        match self {
+            Op::Eq => lhs == rhs,
+            Op::NEq => lhs != rhs,
+            Op::Lt => lhs < rhs,
+            Op::LtEq => lhs <= rhs,
+            Op::GtEq => lhs >= rhs,
+            Op::Gt => lhs > rhs,
+        }
+    }
+}
+
 impl Display for Op {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -136,6 +318,42 @@ pub enum Field {
     Arrive,
     Block,
     Credit,
+    // worked out from the others, see Trip::get
+    Duty,
+    Sit,
+    CreditPerDay,
+}
+
+impl Field {
+    pub const ALL: [Field; 8] = [
+        Field::Report,
+        Field::Depart,
+        Field::Arrive,
+        Field::Block,
+        Field::Credit,
+        Field::Duty,
+        Field::Sit,
+        Field::CreditPerDay,
+    ];
+
+    // Report, Depart and Arrive are times of day, the rest are durations
+    pub fn is_clock(&self) -> bool {
+        matches!(self, Field::Report | Field::Depart | Field::Arrive)
+    }
+
+    // how the field is written in rule text
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Field::Report => "report",
+            Field::Depart => "depart",
+            Field::Arrive => "arrive",
+            Field::Block => "block",
+            Field::Credit => "credit",
+            Field::Duty => "duty",
+            Field::Sit => "sit",
+            Field::CreditPerDay => "credit_per_day",
+        }
+    }
 }
 
 impl Display for Field {
@@ -149,6 +367,9 @@ impl Display for Field {
                 Field::Arrive => "Arrive",
                 Field::Block => "Block",
                 Field::Credit => "Credit",
+                Field::Duty => "Duty",
+                Field::Sit => "Sit",
+                Field::CreditPerDay => "Credit/Day",
             }
         )
     }
@@ -158,12 +379,29 @@ impl Display for Field {
 pub enum Filter {
     TimeDiff(Field, Field, Op, Time),
     FieldIs(Field, Op, Time),
-    DateIs(Op, Date),
-    IncludeLayover(String),
-    ExcludeLayover(String),
+    DateIs(Op, DateExpr),
+    // inclusive on both ends
+    DateBetween(DateExpr, DateExpr),
+    OnWeekday(Vec<Weekday>),
+    // passes when any day of the trip is one of the dates
+    Overlaps(Vec<DateExpr>),
+    EndsBefore(DateExpr),
+    EndDateIs(Op, DateExpr),
+    // passes when the trip fits around our schedule with at least this much rest
+    NoConflict(Time),
+    // airport codes or names of airport groups from config.json
+    IncludeLayover(Vec<String>),
+    ExcludeLayover(Vec<String>),
+    // lays over outside the home country
+    IsInternational,
     NumDays(Op, u8),
+    NumLayovers(Op, u8),
     IsPrem,
     IncludeId(String),
+    All(Vec<Filter>),
+    Any(Vec<Filter>),
+    // passes when the grouped filters do not all pass
+    Not(Vec<Filter>),
 }
 
 impl Filter {
@@ -172,69 +410,202 @@ impl Filter {
             Filter::TimeDiff(_, _, _, _) => "TimeDiff",
             Filter::FieldIs(_, _, _) => "FieldIs",
             Filter::DateIs(_, _) => "DateIs",
+            Filter::DateBetween(_, _) => "DateBetween",
+            Filter::OnWeekday(_) => "Weekday",
+            Filter::Overlaps(_) => "Overlaps",
+            Filter::EndsBefore(_) => "EndsBefore",
+            Filter::EndDateIs(_, _) => "EndDateIs",
+            Filter::NoConflict(_) => "NoConflict",
             Filter::IncludeLayover(_) => "IncludeLay",
             Filter::ExcludeLayover(_) => "ExcludeLay",
+            Filter::IsInternational => "IsIntl",
             Filter::NumDays(_, _) => "NumDays",
+            Filter::NumLayovers(_, _) => "NumLays",
             Filter::IsPrem => "IsPrem",
             Filter::IncludeId(_) => "IsID",
+            Filter::All(_) => "All",
+            Filter::Any(_) => "Any",
+            Filter::Not(_) => "Not",
         }
     }
 
-    pub fn as_string(&self) -> String {
+    pub fn children(&self) -> Option<&Vec<Filter>> {
         match self {
-            Filter::TimeDiff(lhs, rhs, op, t) => format!("{} - {} {} {}", lhs, rhs, op, t),
-            Filter::FieldIs(f, op, t) => format!("{} {} {}", f, op, t),
-            Filter::DateIs(op, d) => format!("Date {} {}", op, d),
-            Filter::IncludeLayover(s) => format!("Include [{}]", s),
-            Filter::ExcludeLayover(s) => format!("Exclude [{}]", s),
-            Filter::NumDays(op, num) => format!("Days {} {}", op, num),
-            Filter::IsPrem => "Is Premium".to_owned(),
-            Filter::IncludeId(s) => format!("Trip ID is \"{}\"", s),
+            Filter::All(filters) | Filter::Any(filters) | Filter::Not(filters) => Some(filters),
+            _ => None,
         }
     }
 
-    pub fn eval(&self, trip: &Trip) -> bool {
+    pub fn children_mut(&mut self) -> Option<&mut Vec<Filter>> {
         match self {
-            Filter::TimeDiff(lhs, rhs, op, val) => match op {
-                Op::Eq => trip.get(*lhs) - trip.get(*rhs) == *val,
-                Op::NEq => trip.get(*lhs) - trip.get(*rhs) != *val,
-                Op::Lt => trip.get(*lhs) - trip.get(*rhs) < *val,
-                Op::LtEq => trip.get(*lhs) - trip.get(*rhs) <= *val,
-                Op::Gt => trip.get(*lhs) - trip.get(*rhs) > *val,
-                Op::GtEq => trip.get(*lhs) - trip.get(*rhs) >= *val,
-            },
-            Filter::FieldIs(field, op, val) => match op {
-                Op::Eq => trip.get(*field) == *val,
-                Op::NEq => trip.get(*field) != *val,
-                Op::Lt => trip.get(*field) < *val,
-                Op::LtEq => trip.get(*field) <= *val,
-                Op::Gt => trip.get(*field) > *val,
-                Op::GtEq => trip.get(*field) >= *val,
-            },
-            Filter::DateIs(op, val) => match op {
-                Op::Eq => trip.date == *val,
-                Op::NEq => trip.date != *val,
-                Op::Lt => trip.date < *val,
-                Op::LtEq => trip.date <= *val,
-                Op::Gt => trip.date > *val,
-                Op::GtEq => trip.date >= *val,
+            Filter::All(filters) | Filter::Any(filters) | Filter::Not(filters) => Some(filters),
+            _ => None,
+        }
+    }
+
+    // the rule text form, see dsl.rs, so parsing it gives back an equivalent filter
+    pub fn as_string(&self) -> String {
+        match self {
+            Filter::TimeDiff(lhs, rhs, op, t) => {
+                format!("{} - {} {} {}", lhs.keyword(), rhs.keyword(), op, t)
+            }
+            Filter::FieldIs(f, op, t) => format!("{} {} {}", f.keyword(), op, t),
+            Filter::DateIs(op, d) => format!("date {} {}", op, d),
+            Filter::DateBetween(from, to) => format!("date between {} and {}", from, to),
+            Filter::OnWeekday(days) => format!("weekday in {}", join_list(days)),
+            Filter::Overlaps(dates) => format!("overlaps {}", join_list(dates)),
+            Filter::EndsBefore(d) => format!("ends before {}", d),
+            Filter::EndDateIs(op, d) => format!("end date {} {}", op, d),
+            Filter::NoConflict(rest) if *rest == Time::default() => "no conflict".to_owned(),
+            Filter::NoConflict(rest) => format!("no conflict rest {}", rest),
+            Filter::IncludeLayover(codes) => format!("layover {}", join_codes(codes)),
+            Filter::ExcludeLayover(codes) => format!("no layover {}", join_codes(codes)),
+            Filter::IsInternational => "international".to_owned(),
+            Filter::NumDays(op, num) => format!("days {} {}", op, num),
+            Filter::NumLayovers(op, num) => format!("layovers {} {}", op, num),
+            Filter::IsPrem => "premium".to_owned(),
+            Filter::IncludeId(s) => format!("trip {}", quote_word(s)),
+            Filter::All(filters) => format!("({})", join_filters(filters, " and ")),
+            // (x) would read back as All, which passes the same trips
+            Filter::Any(filters) if filters.is_empty() => "never".to_owned(),
+            Filter::Any(filters) => format!("({})", join_filters(filters, " or ")),
+            Filter::Not(filters) => match &filters[..] {
+                [f] if !matches!(f, Filter::All(_)) => format!("not {}", f.as_string()),
+                _ => format!("not ({})", join_filters(filters, " and ")),
             },
-            Filter::IncludeLayover(val) => trip.layovers.contains(val),
-            Filter::ExcludeLayover(val) => !trip.layovers.contains(val),
-            Filter::NumDays(op, val) => match op {
-                Op::Eq => trip.days == *val,
-                Op::NEq => trip.days != *val,
-                Op::Lt => trip.days < *val,
-                Op::LtEq => trip.days <= *val,
-                Op::Gt => trip.days > *val,
-                Op::GtEq => trip.days >= *val,
+        }
+    }
+
+    // the trip values this filter compares against, for traces
+    pub fn actual(&self, trip: &Trip, ctx: &EvalContext) -> String {
+        match self {
+            Filter::TimeDiff(lhs, rhs, _, _) => format!(
+                "{} - {} = {}",
+                trip.get(*lhs),
+                trip.get(*rhs),
+                trip.span(*lhs, *rhs)
+            ),
+            Filter::FieldIs(f, _, _) => format!("{} {}", f, trip.get(*f)),
+            Filter::DateIs(_, _) | Filter::DateBetween(_, _) => format!("Date {}", trip.date),
+            Filter::OnWeekday(_) => format!("Date {} {}", trip.date.weekday(), trip.date),
+            Filter::Overlaps(_) => format!("Dates {} to {}", trip.date, trip.end_date()),
+            Filter::EndsBefore(_) | Filter::EndDateIs(_, _) => format!("Ends {}", trip.end_date()),
+            Filter::NoConflict(rest) => match ctx.schedule.conflict(trip, *rest) {
+                Some(item) => format!("Conflicts with {}", item),
+                None => "No conflicts".to_owned(),
             },
+            Filter::IncludeLayover(_) | Filter::ExcludeLayover(_) => {
+                format!("Layovers [{}]", trip.layovers.join(" "))
+            }
+            Filter::IsInternational => format!(
+                "Layovers [{}]",
+                trip.layovers
+                    .iter()
+                    .map(|l| format!("{} {}", l, airports::country(l).unwrap_or("?")))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Filter::NumDays(_, _) => format!("Days {}", trip.days),
+            Filter::NumLayovers(_, _) => format!("Layovers {}", trip.layovers.len()),
+            Filter::IsPrem => format!("Premium {}", trip.premium),
+            Filter::IncludeId(_) => format!("Trip ID \"{}\"", trip.id),
+            Filter::All(_) | Filter::Any(_) | Filter::Not(_) => String::new(),
+        }
+    }
+
+    // why a time filter compares a time of day with a duration, the parser and the gui refuse
+    // to build these and any loaded from a file never pass
+    pub fn mixed_times(&self) -> Option<&'static str> {
+        match self {
+            Filter::TimeDiff(lhs, rhs, _, _) if lhs.is_clock() != rhs.is_clock() => {
+                Some("a time of day and a duration can't be subtracted")
+            }
+            Filter::FieldIs(field, _, val) if field.is_clock() && !val.is_clock() => {
+                Some("expected a time of day, 00:00 to 23:59")
+            }
+            _ => None,
+        }
+    }
+
+    pub fn eval(&self, trip: &Trip, ctx: &EvalContext) -> bool {
+        match self {
+            Filter::TimeDiff(..) | Filter::FieldIs(..) if self.mixed_times().is_some() => false,
+            Filter::TimeDiff(lhs, rhs, op, val) => op.compare(trip.span(*lhs, *rhs), *val),
+            // departures and arrivals after midnight are later than any time before it
+            Filter::FieldIs(field @ (Field::Depart | Field::Arrive), op, val) => {
+                op.compare(trip.after_report(trip.get(*field)), trip.after_report(*val))
+            }
+            Filter::FieldIs(field, op, val) => op.compare(trip.get(*field), *val),
+            Filter::DateIs(op, val) => op.compare(trip.date, val.resolve(ctx.today)),
+            Filter::DateBetween(from, to) => {
+                from.resolve(ctx.today) <= trip.date && trip.date <= to.resolve(ctx.today)
+            }
+            Filter::OnWeekday(days) => days.contains(&trip.date.weekday()),
+            Filter::Overlaps(dates) => {
+                let end = trip.end_date();
+                dates
+                    .iter()
+                    .map(|d| d.resolve(ctx.today))
+                    .any(|d| trip.date <= d && d <= end)
+            }
+            Filter::EndsBefore(val) => trip.end_date() < val.resolve(ctx.today),
+            Filter::EndDateIs(op, val) => op.compare(trip.end_date(), val.resolve(ctx.today)),
+            Filter::NoConflict(rest) => ctx.schedule.conflict(trip, *rest).is_none(),
+            Filter::IncludeLayover(codes) => ctx.airports.any_layover(codes, trip),
+            Filter::ExcludeLayover(codes) => !ctx.airports.any_layover(codes, trip),
+            Filter::IsInternational => ctx.airports.is_international(trip),
+            Filter::NumDays(op, val) => op.compare(trip.days, *val),
+            Filter::NumLayovers(op, val) => op.compare(trip.layovers.len(), *val as usize),
             Filter::IsPrem => trip.premium,
             Filter::IncludeId(val) => trip.id == *val,
+            Filter::All(filters) => filters.iter().all(|f| f.eval(trip, ctx)),
+            Filter::Any(filters) => filters.iter().any(|f| f.eval(trip, ctx)),
+            Filter::Not(filters) => !filters.iter().all(|f| f.eval(trip, ctx)),
         }
     }
 }
 
+fn join_list<T: Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn join_filters(filters: &[Filter], sep: &str) -> String {
+    filters
+        .iter()
+        .map(|f| f.as_string())
+        .collect::<Vec<String>>()
+        .join(sep)
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// an empty list can't be left blank or the next word would be read as a code
+fn join_codes(codes: &[String]) -> String {
+    if codes.is_empty() {
+        return "none".to_owned();
+    }
+    codes
+        .iter()
+        .map(|c| quote_word(c))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// codes that aren't a single plain word get quoted
+fn quote_word(s: &str) -> String {
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        s.to_owned()
+    } else {
+        quote(s)
+    }
+}
+
 impl From<FilterType> for Filter {
     fn from(value: FilterType) -> Self {
         match value {
@@ -243,12 +614,26 @@ impl From<FilterType> for Filter {
                 Filter::TimeDiff(Field::Report, Field::Report, Op::Eq, Time::default())
             }
             FilterType::FieldIs => Filter::FieldIs(Field::Report, Op::Eq, Time::default()),
-            FilterType::DateIs => Filter::DateIs(Op::Eq, Date::default()),
-            FilterType::IncludeLayover => Filter::IncludeLayover(String::new()),
-            FilterType::ExcludeLayover => Filter::ExcludeLayover(String::new()),
+            FilterType::DateIs => Filter::DateIs(Op::Eq, DateExpr::Today(0)),
+            FilterType::DateBetween => Filter::DateBetween(DateExpr::Today(0), DateExpr::Today(7)),
+            FilterType::OnWeekday => Filter::OnWeekday(Vec::new()),
+            FilterType::Overlaps => Filter::Overlaps(Vec::new()),
+            FilterType::EndsBefore => Filter::EndsBefore(DateExpr::Today(7)),
+            FilterType::EndDateIs => Filter::EndDateIs(Op::LtEq, DateExpr::Today(7)),
+            FilterType::NoConflict => Filter::NoConflict(Time {
+                hours: 10,
+                minutes: 0,
+            }),
+            FilterType::IncludeLayover => Filter::IncludeLayover(Vec::new()),
+            FilterType::ExcludeLayover => Filter::ExcludeLayover(Vec::new()),
+            FilterType::IsInternational => Filter::IsInternational,
             FilterType::NumDays => Filter::NumDays(Op::Eq, 1),
+            FilterType::NumLayovers => Filter::NumLayovers(Op::Eq, 1),
             FilterType::IsPrem => Filter::IsPrem,
-            FilterType::IncludeId => Filter::IncludeLayover(String::new()),
+            FilterType::IncludeId => Filter::IncludeId(String::new()),
+            FilterType::All => Filter::All(Vec::new()),
+            FilterType::Any => Filter::Any(Vec::new()),
+            FilterType::Not => Filter::Not(Vec::new()),
         }
     }
 }
@@ -259,11 +644,22 @@ pub enum FilterType {
     TimeDiff,
     FieldIs,
     DateIs,
+    DateBetween,
+    OnWeekday,
+    Overlaps,
+    EndsBefore,
+    EndDateIs,
+    NoConflict,
     IncludeLayover,
     ExcludeLayover,
+    IsInternational,
     NumDays,
+    NumLayovers,
     IsPrem,
     IncludeId,
+    All,
+    Any,
+    Not,
 }
 
 impl Display for FilterType {
@@ -276,11 +672,22 @@ impl Display for FilterType {
                 FilterType::TimeDiff => "TimeDiff",
                 FilterType::FieldIs => "FieldIs",
                 FilterType::DateIs => "DateIs",
+                FilterType::DateBetween => "DateBetween",
+                FilterType::OnWeekday => "Weekday",
+                FilterType::Overlaps => "Overlaps",
+                FilterType::EndsBefore => "EndsBefore",
+                FilterType::EndDateIs => "EndDateIs",
+                FilterType::NoConflict => "NoConflict",
                 FilterType::IncludeLayover => "IncludeLay",
                 FilterType::ExcludeLayover => "ExcludeLay",
+                FilterType::IsInternational => "IsIntl",
                 FilterType::NumDays => "NumDays",
+                FilterType::NumLayovers => "NumLays",
                 FilterType::IsPrem => "IsPrem",
                 FilterType::IncludeId => "IsID",
+                FilterType::All => "All",
+                FilterType::Any => "Any",
+                FilterType::Not => "Not",
             }
         )
     }
@@ -310,9 +717,10 @@ impl Display for BotAction {
     }
 }
 
+// either a time of day or a duration, durations can go past 24 hours (block and credit totals)
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
 pub struct Time {
-    pub hours: u8,
+    pub hours: u16,
     pub minutes: u8,
 }
 
@@ -326,69 +734,56 @@ impl Default for Time {
 }
 
 impl Time {
-    pub fn from_num_str(s: &str) -> Result<Self, ParseTimeError> {
-        if s.len() == 4 {
-            Ok(Time {
-                hours: s[0..2].parse().or(Err(ParseTimeError))?,
-                minutes: s[2..4].parse().or(Err(ParseTimeError))?,
-            })
-        } else if s.len() == 5 {
-            Ok(Time {
-                hours: s[0..2].parse().or(Err(ParseTimeError))?,
-                minutes: s[3..5].parse().or(Err(ParseTimeError))?,
-            })
-        } else {
-            Err(ParseTimeError)
+    pub fn from_minutes(minutes: u32) -> Self {
+        Time {
+            hours: (minutes / 60) as u16,
+            minutes: (minutes % 60) as u8,
         }
     }
-}
 
-impl Sub for Time {
-    type Output = Self;
+    pub fn minutes(&self) -> u32 {
+        self.hours as u32 * 60 + self.minutes as u32
+    }
 
-    fn sub(self, rhs: Self) -> Self {
-        if rhs.minutes > self.minutes {
-            Time {
-                hours: if rhs.hours > self.hours {
-                    self.hours - rhs.hours - 1
-                } else {
-                    12 + self.hours - rhs.hours - 1
-                },
-                minutes: 60 + self.minutes - rhs.minutes,
-            }
-        } else {
-            Time {
-                hours: if rhs.hours > self.hours {
-                    self.hours - rhs.hours
-                } else {
-                    12 + self.hours - rhs.hours
-                },
-                minutes: self.minutes - rhs.minutes,
-            }
-        }
+    pub fn is_clock(&self) -> bool {
+        self.hours < 24
     }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct ParseTimeError;
 
+// accepts "HH:MM" or "HHMM", with any number of hour digits
 impl FromStr for Time {
     type Err = ParseTimeError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() == 4 {
-            Ok(Time {
-                hours: s[0..2].parse().or(Err(ParseTimeError))?,
-                minutes: s[2..4].parse().or(Err(ParseTimeError))?,
-            })
-        } else if s.len() == 5 {
-            Ok(Time {
-                hours: s[0..2].parse().or(Err(ParseTimeError))?,
-                minutes: s[3..5].parse().or(Err(ParseTimeError))?,
-            })
-        } else {
-            Err(ParseTimeError)
+        if !s.is_ascii() {
+            return Err(ParseTimeError);
+        }
+        let (hours, minutes) = match s.split_once(':') {
+            Some(parts) => parts,
+            None if s.len() >= 3 => s.split_at(s.len() - 2),
+            None => return Err(ParseTimeError),
+        };
+        if hours.is_empty()
+            || minutes.len() != 2
+            || !hours
+                .chars()
+                .chain(minutes.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(ParseTimeError);
+        }
+
+        let minutes: u8 = minutes.parse().or(Err(ParseTimeError))?;
+        if minutes >= 60 {
+            return Err(ParseTimeError);
         }
+        Ok(Time {
+            hours: hours.parse().or(Err(ParseTimeError))?,
+            minutes,
+        })
     }
 }
 
@@ -418,18 +813,71 @@ impl Default for Date {
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct ParseDateError;
 
+impl Date {
+    pub fn today() -> Self {
+        Date::from_naive(Local::now().date_naive())
+    }
+
+    pub fn from_naive(date: NaiveDate) -> Self {
+        Date {
+            year: date.year() as u16,
+            month: date.month() as u8,
+            day: date.day() as u8,
+        }
+    }
+
+    pub fn to_naive(self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(self.year as i32, self.month as u32, self.day as u32)
+    }
+
+    pub fn weekday(self) -> Weekday {
+        match self.to_naive().map(|d| d.weekday()) {
+            Some(chrono::Weekday::Mon) => Weekday::Mon,
+            Some(chrono::Weekday::Tue) => Weekday::Tue,
+            Some(chrono::Weekday::Wed) => Weekday::Wed,
+            Some(chrono::Weekday::Thu) => Weekday::Thu,
+            Some(chrono::Weekday::Fri) => Weekday::Fri,
+            Some(chrono::Weekday::Sat) => Weekday::Sat,
+            Some(chrono::Weekday::Sun) | None => Weekday::Sun,
+        }
+    }
+
+    // days since 1 JAN, 1 CE
+    pub fn day_number(self) -> i64 {
+        self.to_naive().map_or(0, |d| d.num_days_from_ce() as i64)
+    }
+
+    pub fn add_days(self, days: i64) -> Self {
+        self.to_naive()
+            .and_then(|d| d.checked_add_signed(TimeDelta::days(days)))
+            .map_or(self, Date::from_naive)
+    }
+
+    // resolves a year-less "05JAN" to the first such date on or after `reference`
+    pub fn parse_from(s: &str, reference: Date) -> Result<Self, ParseDateError> {
+        if s.len() != 5 || !s.is_ascii() {
+            return Err(ParseDateError);
+        }
+        let month = month_from_str(&s[2..5])?;
+        let day = s[0..2].parse().or(Err(ParseDateError))?;
+
+        // 29FEB can be up to 4 years away
+        (reference.year..reference.year + 8)
+            .map(|year| Date { year, month, day })
+            .find(|d| d.to_naive().is_some() && *d >= reference)
+            .ok_or(ParseDateError)
+    }
+}
+
+// accepts an ISO "2026-01-05", or a year-less "05JAN" taken to be the next one from today
 impl FromStr for Date {
     type Err = ParseDateError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() != 5 {
-            return Err(ParseDateError);
+        match NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            Ok(d) => Ok(Date::from_naive(d)),
+            Err(_) => Date::parse_from(s, Date::today()),
         }
-        Ok(Date {
-            year: 2025,
-            month: month_from_str(&s[2..5])?,
-            day: s[0..2].parse().or(Err(ParseDateError))?,
-        })
     }
 }
 
@@ -477,18 +925,96 @@ impl Display for Date {
     }
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl Display for Weekday {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Weekday::Mon => "Mon",
+                Weekday::Tue => "Tue",
+                Weekday::Wed => "Wed",
+                Weekday::Thu => "Thu",
+                Weekday::Fri => "Fri",
+                Weekday::Sat => "Sat",
+                Weekday::Sun => "Sun",
+            }
+        )
+    }
+}
+
+// a date in a rule, either fixed or counted from the day the rule is checked
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DateExpr {
+    Today(i32),
+    On(Date),
+}
+
+impl DateExpr {
+    pub fn resolve(&self, today: Date) -> Date {
+        match self {
+            DateExpr::Today(days) => today.add_days(*days as i64),
+            DateExpr::On(date) => *date,
+        }
+    }
+}
+
+// "today", "today+3", "today-1" or anything Date accepts
+impl FromStr for DateExpr {
+    type Err = ParseDateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.get(..5) {
+            Some(t) if t.eq_ignore_ascii_case("today") => {
+                let offset = s[5..].replace(' ', "");
+                if offset.is_empty() {
+                    Ok(DateExpr::Today(0))
+                } else if offset.starts_with(['+', '-']) {
+                    offset.parse().map(DateExpr::Today).or(Err(ParseDateError))
+                } else {
+                    Err(ParseDateError)
+                }
+            }
+            _ => s.parse().map(DateExpr::On),
+        }
+    }
+}
+
+impl Display for DateExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DateExpr::Today(0) => write!(f, "today"),
+            DateExpr::Today(days) => write!(f, "today{:+}", days),
+            DateExpr::On(d) => write!(f, "{:04}-{:02}-{:02}", d.year, d.month, d.day),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Trip {
-    id: String,
-    date: Date,
-    days: u8,
-    report: Time,
-    depart: Time,
-    arrive: Time,
-    block: Time,
-    credit: Time,
-    layovers: Vec<String>,
-    premium: bool,
+    pub id: String,
+    pub date: Date,
+    pub days: u8,
+    pub report: Time,
+    pub depart: Time,
+    pub arrive: Time,
+    pub block: Time,
+    pub credit: Time,
+    pub layovers: Vec<String>,
+    pub premium: bool,
 }
 
 impl Trip {
@@ -499,10 +1025,134 @@ impl Trip {
             Field::Arrive => self.arrive,
             Field::Block => self.block,
             Field::Credit => self.credit,
+            // the board has no legs, only the first report, the last arrival and the total
+            // block, so duty periods can't be told apart, this is exact for a one day trip
+            // but runs through the layovers of a longer one
+            Field::Duty => self.span(Field::Report, Field::Arrive),
+            // Duty not spent flying, so layovers count as sit on trips over a day
+            Field::Sit => Time::from_minutes(
+                self.get(Field::Duty)
+                    .minutes()
+                    .saturating_sub(self.block.minutes()),
+            ),
+            Field::CreditPerDay => {
+                Time::from_minutes(self.credit.minutes() / self.days.max(1) as u32)
+            }
+        }
+    }
+
+    // minutes since midnight on the first day of the trip for times of day,
+    // so an arrival after midnight or on a later day is still after the report
+    pub fn offset(&self, field: Field) -> u32 {
+        const DAY: u32 = 24 * 60;
+
+        let report = self.report.minutes();
+        let mut depart = self.depart.minutes();
+        if depart < report {
+            depart += DAY;
+        }
+
+        match field {
+            Field::Report => report,
+            Field::Depart => depart,
+            Field::Arrive => {
+                let mut arrive = self.days.saturating_sub(1) as u32 * DAY + self.arrive.minutes();
+                while arrive < depart {
+                    arrive += DAY;
+                }
+                arrive
+            }
+            _ => self.get(field).minutes(),
+        }
+    }
+
+    // a time of day in minutes on a clock that starts at the report time,
+    // times earlier than the report are after midnight, like depart in offset
+    pub fn after_report(&self, time: Time) -> u32 {
+        const DAY: u32 = 24 * 60;
+
+        let minutes = time.minutes() % DAY;
+        if minutes < self.report.minutes() {
+            minutes + DAY
+        } else {
+            minutes
+        }
+    }
+
+    // the day the trip arrives back, `days` counts the report day
+    pub fn end_date(&self) -> Date {
+        self.date.add_days(self.days.saturating_sub(1) as i64)
+    }
+
+    // time between two fields regardless of order
+    pub fn span(&self, lhs: Field, rhs: Field) -> Time {
+        Time::from_minutes(self.offset(lhs).abs_diff(self.offset(rhs)))
+    }
+}
+
+// the winning rule depends on the set's match mode,
+// a trip that overlaps our schedule or goes over the credit cap is never picked up
+// whatever the rules say
+pub fn decide<'a>(
+    set: &'a RuleSet,
+    trip: &Trip,
+    ctx: &EvalContext,
+) -> (BotAction, Option<&'a Rule>) {
+    let mut matching = set.rules.iter().filter(|r| r.enabled && r.eval(trip, ctx));
+    let rule = match set.mode {
+        MatchMode::FirstMatch => matching.next(),
+        // max_by_key keeps the last of equal keys, so go backwards to keep the first
+        MatchMode::Priority => matching.rev().max_by_key(|r| r.priority),
+    };
+
+    match rule.map_or(BotAction::Nothing, |r| r.action) {
+        BotAction::Pickup
+            if ctx.schedule.conflict(trip, Time::default()).is_some()
+                || !ctx.credit.allows(trip, ctx.today) =>
+        {
+            (BotAction::Alert, rule)
         }
+        action => (action, rule),
     }
 }
 
+// the sum of the points of every enabled rule the trip matches
+pub fn score(set: &RuleSet, trip: &Trip, ctx: &EvalContext) -> i32 {
+    set.rules
+        .iter()
+        .filter(|r| r.enabled && r.eval(trip, ctx))
+        .map(|r| r.points_for(trip))
+        .sum()
+}
+
+// traces each trip, best score first when the set is ranked, page order otherwise,
+// a trip that would be picked up counts towards the credit cap and is on the schedule
+// of the trips after it
+fn evaluate<'a>(
+    set: &RuleSet,
+    trips: impl Iterator<Item = &'a Trip>,
+    ctx: &EvalContext,
+) -> Vec<(&'a Trip, TripTrace)> {
+    let mut ranked: Vec<(&Trip, i32)> = trips.map(|t| (t, score(set, t, ctx))).collect();
+    if set.ranked {
+        // stable, so trips with equal scores stay in page order
+        ranked.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    }
+
+    let mut ctx = ctx.clone();
+    ranked
+        .into_iter()
+        .map(|(trip, _)| {
+            let trace = TripTrace::new(set, trip, &ctx);
+            if trace.action == BotAction::Pickup {
+                ctx.credit.tally.record(trip);
+                ctx.schedule.add_trip(trip);
+            }
+            (trip, trace)
+        })
+        .collect()
+}
+
 use std::collections::HashSet;
 
 use xcap::image::{Rgba, RgbaImage};
@@ -521,24 +1171,28 @@ pub struct OpentimeRow {
     pub x_premium: u32,
 }
 
-// TODO: validate this is the first row using the blue header
-// for pairing and premium rows
+// validates the first row using the blue header above
+// the pairing and premium columns
 fn table_moved(screen: &RgbaImage, first_row: &OpentimeRow) -> bool {
     let white = Rgba::<u8>::from([0xFF, 0xFF, 0xFF, 0xFF]);
     let light_gray = Rgba::<u8>::from([0xB2, 0xB2, 0xB2, 0xFF]);
     let dark_gray = Rgba::<u8>::from([0x4C, 0x4C, 0x4C, 0xFF]);
     let light_blue = Rgba::<u8>::from([0xB0, 0xC4, 0xDE, 0xFF]);
 
-    if first_row.h == 0 {
-        return true;
-    } else if first_row.y < 3 {
+    if first_row.h == 0
+        || first_row.y < 3
+        || first_row.x_premium >= screen.width()
+        || first_row.y >= screen.height()
+    {
         return true;
     }
 
-    *screen.get_pixel(first_row.x_pairing, first_row.y) != white
-        || *screen.get_pixel(first_row.x_pairing, first_row.y - 1) != dark_gray
-        || *screen.get_pixel(first_row.x_pairing, first_row.y - 2) != light_gray
-        || *screen.get_pixel(first_row.x_pairing, first_row.y - 3) != light_blue
+    [first_row.x_pairing, first_row.x_premium].iter().any(|x| {
+        *screen.get_pixel(*x, first_row.y) != white
+            || *screen.get_pixel(*x, first_row.y - 1) != dark_gray
+            || *screen.get_pixel(*x, first_row.y - 2) != light_gray
+            || *screen.get_pixel(*x, first_row.y - 3) != light_blue
+    })
 }
 
 fn find_first_row(screen: &RgbaImage) -> Option<OpentimeRow> {
@@ -651,8 +1305,6 @@ fn find_first_row(screen: &RgbaImage) -> Option<OpentimeRow> {
     };
     println!("{:?}", dim);
 
-    save_row(screen, &dim, 999);
-
     Some(dim)
 }
 
@@ -683,20 +1335,58 @@ fn collect_prem(screen: &RgbaImage, first_row: &OpentimeRow) -> Vec<OpentimeRow>
     prem_rows
 }
 
-fn save_row(screen: &RgbaImage, row: &OpentimeRow, id: usize) {
-    let mut out_img = RgbaImage::new(row.w_pairing + 1, row.h);
+// rows are found from the top of the table so their index matches the order of the copied text
+fn row_index(first_row: &OpentimeRow, row: &OpentimeRow) -> usize {
+    ((row.y - first_row.y) / (first_row.h + TABLE_LINE_WIDTH * 2)) as usize
+}
 
-    for y in 0..row.h {
-        for x in 0..row.w_pairing {
-            out_img[(x, y)] = screen[(x + row.x_pairing, y + row.y)];
-        }
-    }
+// the premium rows on screen have to be the premium trips at the top of the copied text,
+// otherwise the table scrolled, lost its header or has a wrapped row and a highlighted
+// row could be matched to a different trip
+fn rows_match(page: &ParsedPage, first_row: &OpentimeRow, prem_rows: &[OpentimeRow]) -> bool {
+    let on_screen: Vec<usize> = prem_rows.iter().map(|r| row_index(first_row, r)).collect();
+    let in_text: Vec<usize> = (0..CAP_LEN)
+        .filter(|i| page.row(*i).is_some_and(|t| t.premium))
+        .collect();
+    on_screen == in_text
+}
 
-    for y in 0..row.h {
-        out_img[(row.w_pairing, y)] = screen[(row.x_premium, y + row.y)];
-    }
+fn row_trip<'a>(
+    page: &'a ParsedPage,
+    first_row: &OpentimeRow,
+    row: &OpentimeRow,
+) -> Option<&'a Trip> {
+    page.row(row_index(first_row, row)).filter(|t| t.premium)
+}
 
-    out_img.save(format!("row-{id}.png"));
+// select and copy the whole page, then click at `deselect` to clear the highlight
+fn copy_page_text(
+    input: &mut impl Input,
+    clipboard: &mut impl Clipboard,
+    deselect: (i32, i32),
+) -> Result<String, ClipboardError> {
+    let input_failed = |e: InputError| ClipboardError::Input(e.to_string());
+    let text = copy_text(
+        clipboard,
+        || {
+            input
+                .chord(&[Key::Control], Key::Unicode('a'))
+                .and_then(|_| input.chord(&[Key::Control], Key::Unicode('c')))
+                .map_err(input_failed)
+        },
+        Duration::from_millis(1000),
+    );
+
+    let deselected = input.click(deselect.0, deselect.1);
+    input.wait(Duration::from_millis(150));
+
+    let text = text?;
+    deselected.map_err(input_failed)?;
+    Ok(text)
+}
+
+fn refresh_page(input: &mut impl Input) -> enigo::InputResult<()> {
+    input.chord(&[Key::Control], Key::Unicode('r'))
 }
 
 fn is_ignored(screen: &RgbaImage, row: &OpentimeRow, ignored_trips: &HashSet<RgbaImage>) -> bool {
@@ -721,51 +1411,40 @@ fn ignore_trip(screen: &RgbaImage, row: &OpentimeRow, ignored_trips: &mut HashSe
     ignored_trips.insert(pairing_id);
 }
 
-fn bot_main(ignored_trips: &mut HashSet<RgbaImage>) -> Option<Vec<OpentimeRow>> {
-    let screen = xcap::Monitor::all().unwrap()[0].clone();
-    let cap = screen.capture_image().unwrap();
-
-    let mut first_row: OpentimeRow = find_first_row(&cap)?;
-
-    loop {
-        if table_moved(&cap, &first_row) {
-            first_row = find_first_row(&cap)?;
-        }
-
-        let prem_trips = collect_prem(&cap, &first_row);
-        if prem_trips.is_empty() {
-            // continue
-            None?;
-        } else {
-            let new_prem: Vec<OpentimeRow> = prem_trips
-                .into_iter()
-                .filter(|trip| !is_ignored(&cap, trip, ignored_trips))
-                .collect();
-            if new_prem.is_empty() {
-                // continue
-                None?;
-            } else {
-                new_prem
-                    .iter()
-                    .for_each(|row| ignore_trip(&cap, row, ignored_trips));
-                return Some(new_prem);
-            }
-        }
-    }
-}
-
 pub fn image_bot_thread(rx: Receiver<BotMessage>, tx: Sender<BotMessage>) {
-    let mut state = AppState::Stopped;
-    let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-    let file = BufReader::new(File::open("alert_sound.wav").unwrap());
-    let source = Decoder::new(file).unwrap();
-    let sink = Sink::try_new(&stream_handle).unwrap();
-    sink.append(source.repeat_infinite());
-    sink.pause();
-
+    let mut alarm = SoundAlarm::new("alert_sound.wav").unwrap();
     let config: BotConfig = BotConfig::load().unwrap();
+    let mut input = EnigoInput::new().unwrap();
+    let mut clipboard = SystemClipboard::new().unwrap();
+    let mut capture = config.capture().unwrap();
+
+    run_image_bot(
+        rx,
+        tx,
+        &config,
+        capture.as_mut(),
+        &mut input,
+        &mut clipboard,
+        &mut alarm,
+    );
+}
 
-    let mut enigo = Enigo::new(&Settings::default()).unwrap();
+// returns once the app has gone and the bot isn't running
+fn run_image_bot(
+    rx: Receiver<BotMessage>,
+    tx: Sender<BotMessage>,
+    config: &BotConfig,
+    capture: &mut dyn Capture,
+    input: &mut impl Input,
+    clipboard: &mut impl Clipboard,
+    alarm: &mut impl Alarm,
+) {
+    let mut rules = RuleSet::default();
+    let mut ctx = EvalContext::default();
+    let mut state = AppState::Stopped;
+    let mut dry_run = false;
+    ctx.credit = credit_budget(config, &tx);
+    ctx.airports = config.airports.clone();
 
     let mut missing_count = 0;
     let mut last_refresh = Instant::now();
@@ -773,7 +1452,6 @@ pub fn image_bot_thread(rx: Receiver<BotMessage>, tx: Sender<BotMessage>) {
     thread::sleep(Duration::from_secs(1));
 
     let mut ignored_trips = HashSet::<RgbaImage>::new();
-    let screen = xcap::Monitor::all().unwrap()[0].clone();
 
     let mut first_row = OpentimeRow {
         y: 0,
@@ -785,19 +1463,24 @@ pub fn image_bot_thread(rx: Receiver<BotMessage>, tx: Sender<BotMessage>) {
 
     println!("bot entering main loop");
     'main: loop {
-        if let Ok(msg) = rx.try_recv() {
-            match msg {
-                BotMessage::Start(r) => {
-                    state = AppState::Running;
-                    missing_count = 0;
-                    // rules = r;
+        match rx.try_recv() {
+            Ok(BotMessage::Start(r, schedule, dry)) => {
+                state = AppState::Running;
+                missing_count = 0;
+                rules = r;
+                ctx.schedule = schedule;
+                dry_run = dry;
+                // the tally may have been fixed since
+                if ctx.credit.tally_unknown {
+                    ctx.credit = credit_budget(config, &tx);
                 }
-                BotMessage::Stop => {
-                    state = AppState::Stopped;
-                    sink.pause();
-                }
-                _ => {}
             }
+            Ok(BotMessage::Stop) => {
+                state = AppState::Stopped;
+                alarm.pause();
+            }
+            Err(TryRecvError::Disconnected) if state != AppState::Running => return,
+            _ => {}
         }
 
         if state != AppState::Running {
@@ -816,14 +1499,20 @@ pub fn image_bot_thread(rx: Receiver<BotMessage>, tx: Sender<BotMessage>) {
                 .unwrap();
 
             // refresh page
-            // let _ = enigo.key(Key::F5, Click);
-            let _ = enigo.key(Key::Control, Press);
-            let _ = enigo.key(Key::Unicode('r'), Click);
-            let _ = enigo.key(Key::Control, Release);
+            if let Err(e) = refresh_page(input) {
+                input_stopped(&tx, &mut state, e);
+                continue 'main;
+            }
         }
 
         thread::sleep(Duration::from_millis(1000));
-        let cap = screen.capture_image().unwrap();
+        let cap = match capture.frame() {
+            Ok(cap) => cap,
+            Err(e) => {
+                capture_stopped(&tx, &mut state, e);
+                continue 'main;
+            }
+        };
         // tx.send(BotMessage::Screenshot);
 
         if table_moved(&cap, &first_row) {
@@ -831,9 +1520,9 @@ pub fn image_bot_thread(rx: Receiver<BotMessage>, tx: Sender<BotMessage>) {
                 missing_count += 1;
                 if missing_count > 5 {
                     state = AppState::Stopped;
-                    tx.send(BotMessage::Stop);
+                    tx.send(BotMessage::Stop).unwrap();
                 }
-                tx.send(BotMessage::TableMissing);
+                tx.send(BotMessage::TableMissing).unwrap();
 
                 println!("First row missing");
                 thread::sleep(Duration::from_millis(500));
@@ -844,9 +1533,11 @@ pub fn image_bot_thread(rx: Receiver<BotMessage>, tx: Sender<BotMessage>) {
             first_row = new_first_row;
         }
 
-        let new_prem: Vec<OpentimeRow> = collect_prem(&cap, &first_row)
-            .into_iter()
+        let prem = collect_prem(&cap, &first_row);
+        let new_prem: Vec<OpentimeRow> = prem
+            .iter()
             .filter(|trip| !is_ignored(&cap, trip, &ignored_trips))
+            .cloned()
             .collect();
 
         if new_prem.is_empty() {
@@ -856,52 +1547,156 @@ pub fn image_bot_thread(rx: Receiver<BotMessage>, tx: Sender<BotMessage>) {
                 .iter()
                 .for_each(|row| ignore_trip(&cap, row, &mut ignored_trips));
 
-            // for debugging
-            new_prem
-                .iter()
-                .enumerate()
-                .for_each(|(idx, row)| save_row(&cap, row, idx));
+            ctx.today = Date::today();
+            // the updated time is plain text above the table, clicking inside the
+            // table would open a trip and clicking the header would sort it
+            let (x, y, w, h) = config.updated_time_pos;
+            let deselect = (x + w as i32 / 2, y + h as i32 / 2);
+            let page = match copy_page_text(input, clipboard, deselect) {
+                Ok(text) => opentime::parse(&text, ctx.today),
+                Err(e) => {
+                    println!("{}", e);
+                    tx.send(BotMessage::CopyFailed(e)).unwrap();
+                    ParsedPage::default()
+                }
+            };
+            for d in &page.diagnostics {
+                tx.send(BotMessage::BadRow(d.clone())).unwrap();
+            }
 
-            // Alert user
-            sink.play();
-            state = AppState::Alerting;
-            tx.send(BotMessage::TripFound).unwrap();
+            let mut alert = false;
+            let mut trips = Vec::new();
+            let matched = rows_match(&page, &first_row, &prem);
+            if !matched {
+                // picking up the wrong trip is worse than asking
+                println!("premium rows on screen don't match the copied text");
+                alert = true;
+            }
+            for row in new_prem.iter().filter(|_| matched) {
+                match row_trip(&page, &first_row, row) {
+                    Some(trip) => trips.push(trip),
+                    None => {
+                        // the row could not be read, so let the user decide
+                        println!("no trip found for row {:?}", row);
+                        alert = true;
+                    }
+                }
+            }
+
+            for (trip, trace) in evaluate(&rules, trips.into_iter(), &ctx) {
+                let action = trace.action;
+                println!("{:?} {}", action, trip.id);
+                tx.send(BotMessage::Evaluated(trace)).unwrap();
+                if action == BotAction::Pickup && dry_run {
+                    simulate_pickup(&tx, &trip.id);
+                } else if action == BotAction::Pickup {
+                    match add_trip_from_opentime(input, &trip.id) {
+                        Ok(()) => record_pickup(&tx, &mut ctx.credit, trip),
+                        Err(e) => {
+                            println!("{}", e);
+                            tx.send(BotMessage::PickupFailed(trip.id.clone(), e))
+                                .unwrap();
+                        }
+                    }
+                    alarm.play();
+                    state = AppState::Stopped;
+                    tx.send(BotMessage::Stop).unwrap();
+                    continue 'main;
+                } else if action == BotAction::Alert {
+                    alert = true;
+                }
+            }
+
+            if alert {
+                // Alert user
+                alarm.play();
+                state = AppState::Alerting;
+                tx.send(BotMessage::TripFound).unwrap();
+            }
         }
     }
 }
 
+// a tally that can't be read is reported and leaves pickups alerting until it can be
+fn credit_budget(config: &BotConfig, tx: &Sender<BotMessage>) -> CreditBudget {
+    let (tally, tally_unknown) = match CreditTally::load("credit.json") {
+        Ok(tally) => (tally, false),
+        Err(e) => {
+            println!("{}", e);
+            tx.send(BotMessage::CreditFailed(e)).unwrap();
+            (CreditTally::default(), true)
+        }
+    };
+    CreditBudget {
+        cap: config.credit_cap,
+        existing: config.existing_credit,
+        tally,
+        tally_unknown,
+    }
+}
+
+fn record_pickup(tx: &Sender<BotMessage>, credit: &mut CreditBudget, trip: &Trip) {
+    credit.tally.record(trip);
+    if let Err(e) = credit.tally.save("credit.json") {
+        println!("{}", e);
+        tx.send(BotMessage::CreditFailed(e)).unwrap();
+    }
+}
+
+// a capture that fails stops the bot, a replay running out of frames is an ordinary stop
+fn capture_stopped(tx: &Sender<BotMessage>, state: &mut AppState, e: CaptureError) {
+    println!("{}", e);
+    *state = AppState::Stopped;
+    if e == CaptureError::Finished {
+        tx.send(BotMessage::ReplayFinished).unwrap();
+    } else {
+        tx.send(BotMessage::CaptureFailed(e)).unwrap();
+    }
+    tx.send(BotMessage::Stop).unwrap();
+}
+
+fn input_stopped(tx: &Sender<BotMessage>, state: &mut AppState, e: InputError) {
+    println!("{}", e);
+    *state = AppState::Stopped;
+    tx.send(BotMessage::InputFailed(e)).unwrap();
+    tx.send(BotMessage::Stop).unwrap();
+}
+
 pub fn bot_thread(rx: Receiver<BotMessage>, tx: Sender<BotMessage>) {
-    let mut rules: Vec<Rule> = Vec::new();
+    let mut rules = RuleSet::default();
+    let mut ctx = EvalContext::default();
     let mut state = AppState::Stopped;
-    let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-    let file = BufReader::new(File::open("alert_sound.wav").unwrap());
-    let source = Decoder::new(file).unwrap();
-    let sink = Sink::try_new(&stream_handle).unwrap();
-    sink.append(source.repeat_infinite());
-    sink.pause();
+    let mut dry_run = false;
+    let mut alarm = SoundAlarm::new("alert_sound.wav").unwrap();
 
     let config: BotConfig = BotConfig::load().unwrap();
-    let re_international: Regex = Regex::new(r"DUB|EDI|LHR|LGW|CDG|AMS").unwrap();
-    let re_opentime_trip: Regex = RegexBuilder::new(r"^(?P<tripid>\w+)\s+(?P<date>\w+)\s+(?P<days>\d+)\s+(?P<report>\S+)\s+(?P<depart>\S+)\s+(?P<arrive>\S+)\s+(?P<bulk>\d+)\s+(?P<credit>\d+)\s+(?P<layovers>(?:\S{3}\s*)*)\s*(?P<prem>X?)\s*$")
-            .multi_line(true)
-            .build()
-            .unwrap()
-    ;
-    let mut enigo = Enigo::new(&Settings::default()).unwrap();
-    let screen = screenshots::Screen::all().unwrap()[0];
-    let mut image_update_time: screenshots::image::RgbaImage = screen
-        .capture_area(
-            config.updated_time_pos.0,
-            config.updated_time_pos.1,
-            config.updated_time_pos.2,
-            config.updated_time_pos.3,
-        )
-        .unwrap();
-    let mut new_update_time: screenshots::image::RgbaImage;
-    let blank = screenshots::image::RgbaImage::from_pixel(
+    ctx.credit = credit_budget(&config, &tx);
+    ctx.airports = config.airports.clone();
+    let mut input = EnigoInput::new().unwrap();
+    let mut clipboard = SystemClipboard::new().unwrap();
+    let mut capture = config.capture().unwrap();
+    let update_time = |capture: &mut Box<dyn Capture>| {
+        let (x, y, w, h) = config.updated_time_pos;
+        match (u32::try_from(x), u32::try_from(y)) {
+            (Ok(ux), Ok(uy)) => capture.area(ux, uy, w, h),
+            _ => Err(CaptureError::OffScreen(x, y)),
+        }
+    };
+    let refresh_icon = |capture: &mut Box<dyn Capture>| {
+        let [x, y, w, h] = config.refresh;
+        capture.area(x, y, w, h)
+    };
+    let mut image_update_time = match update_time(&mut capture) {
+        Ok(image) => image,
+        Err(e) => {
+            capture_stopped(&tx, &mut state, e);
+            return;
+        }
+    };
+    let blank = RgbaImage::from_pixel(
         config.updated_time_pos.2,
         config.updated_time_pos.3,
-        screenshots::image::Rgba([255, 255, 255, 255]),
+        Rgba([255, 255, 255, 255]),
     );
     //image_update_time.save(format!("time.png")).unwrap();
 
@@ -912,41 +1707,48 @@ pub fn bot_thread(rx: Receiver<BotMessage>, tx: Sender<BotMessage>) {
     thread::sleep(Duration::from_secs(1));
 
     // click mouse to focus window
-    let _ = enigo.move_mouse(loc_opentime.0, loc_opentime.1, Coordinate::Abs);
-    let _ = enigo.button(Button::Left, Click);
+    if let Err(e) = input.click(loc_opentime.0, loc_opentime.1) {
+        input_stopped(&tx, &mut state, e);
+        return;
+    }
     thread::sleep(Duration::from_secs(1));
 
-    let mut load_icon = screen
-        .capture_area(
-            config.refresh[0] as i32,
-            config.refresh[1] as i32,
-            config.refresh[2],
-            config.refresh[3],
-        )
-        .unwrap();
+    let mut load_icon = match refresh_icon(&mut capture) {
+        Ok(icon) => icon,
+        Err(e) => {
+            capture_stopped(&tx, &mut state, e);
+            return;
+        }
+    };
 
     println!("bot entering main loop");
     'main: loop {
         if let Ok(msg) = rx.try_recv() {
             match msg {
-                BotMessage::Start(r) => {
+                BotMessage::Start(r, schedule, dry) => {
                     state = AppState::Running;
                     rules = r;
-                    let _ = enigo.move_mouse(loc_opentime.0, loc_opentime.1, Coordinate::Abs);
-                    let _ = enigo.button(Button::Left, Click);
+                    ctx.schedule = schedule;
+                    dry_run = dry;
+                    if ctx.credit.tally_unknown {
+                        ctx.credit = credit_budget(&config, &tx);
+                    }
+                    if let Err(e) = input.click(loc_opentime.0, loc_opentime.1) {
+                        input_stopped(&tx, &mut state, e);
+                        continue 'main;
+                    }
                     thread::sleep(Duration::from_secs(1));
-                    load_icon = screen
-                        .capture_area(
-                            config.refresh[0] as i32,
-                            config.refresh[1] as i32,
-                            config.refresh[2],
-                            config.refresh[3],
-                        )
-                        .unwrap();
+                    load_icon = match refresh_icon(&mut capture) {
+                        Ok(icon) => icon,
+                        Err(e) => {
+                            capture_stopped(&tx, &mut state, e);
+                            continue 'main;
+                        }
+                    };
                 }
                 BotMessage::Stop => {
                     state = AppState::Stopped;
-                    sink.pause();
+                    alarm.pause();
                 }
                 _ => {}
             }
@@ -968,49 +1770,43 @@ pub fn bot_thread(rx: Receiver<BotMessage>, tx: Sender<BotMessage>) {
             tx.send(BotMessage::Waiting(refresh_interval.as_secs()))
                 .unwrap();
             // refresh page
-            let _ = enigo.key(Key::Control, Press);
-            let _ = enigo.key(Key::Unicode('r'), Click);
-            let _ = enigo.key(Key::Control, Release);
+            if let Err(e) = refresh_page(&mut input) {
+                input_stopped(&tx, &mut state, e);
+                continue 'main;
+            }
 
             // wait for page to finish loading
-            while screen
-                .capture_area(
-                    config.refresh[0] as i32,
-                    config.refresh[1] as i32,
-                    config.refresh[2],
-                    config.refresh[3],
-                )
-                .unwrap()
-                != load_icon
-            {
-                thread::sleep(Duration::from_millis(100));
+            loop {
+                match refresh_icon(&mut capture) {
+                    Ok(icon) if icon == load_icon => break,
+                    Ok(_) => thread::sleep(Duration::from_millis(100)),
+                    Err(e) => {
+                        capture_stopped(&tx, &mut state, e);
+                        continue 'main;
+                    }
+                }
             }
             thread::sleep(Duration::from_millis(300));
 
-            new_update_time = screen
-                .capture_area(
-                    config.updated_time_pos.0,
-                    config.updated_time_pos.1,
-                    config.updated_time_pos.2,
-                    config.updated_time_pos.3,
-                )
-                .unwrap();
-            while new_update_time.pixels().eq(blank.pixels()) {
-                new_update_time = screen
-                    .capture_area(
-                        config.updated_time_pos.0,
-                        config.updated_time_pos.1,
-                        config.updated_time_pos.2,
-                        config.updated_time_pos.3,
-                    )
-                    .unwrap();
-                thread::sleep(Duration::from_millis(50));
+            loop {
+                match update_time(&mut capture) {
+                    Ok(time) if time.pixels().eq(blank.pixels()) => {
+                        thread::sleep(Duration::from_millis(50))
+                    }
+                    Ok(_) => break,
+                    Err(e) => {
+                        capture_stopped(&tx, &mut state, e);
+                        continue 'main;
+                    }
+                }
             }
             thread::sleep(Duration::from_millis(500));
 
             // click mouse in proper area
-            let _ = enigo.move_mouse(loc_opentime.0, loc_opentime.1, Coordinate::Abs);
-            let _ = enigo.button(Button::Left, Click);
+            if let Err(e) = input.click(loc_opentime.0, loc_opentime.1) {
+                input_stopped(&tx, &mut state, e);
+                continue 'main;
+            }
             thread::sleep(Duration::from_millis(300));
         }
 
@@ -1018,14 +1814,13 @@ pub fn bot_thread(rx: Receiver<BotMessage>, tx: Sender<BotMessage>) {
         // TODO: compare to blank image to ensure page has finished loading
 
         //println!("checking time");
-        new_update_time = screen
-            .capture_area(
-                config.updated_time_pos.0,
-                config.updated_time_pos.1,
-                config.updated_time_pos.2,
-                config.updated_time_pos.3,
-            )
-            .unwrap();
+        let new_update_time = match update_time(&mut capture) {
+            Ok(time) => time,
+            Err(e) => {
+                capture_stopped(&tx, &mut state, e);
+                continue 'main;
+            }
+        };
 
         if !new_update_time.pixels().eq(image_update_time.pixels()) {
             image_update_time.save("old.png");
@@ -1036,64 +1831,47 @@ pub fn bot_thread(rx: Receiver<BotMessage>, tx: Sender<BotMessage>) {
             println!("Copying screen");
             tx.send(BotMessage::CopyScreen).unwrap();
             // copy text
-            let _ = enigo.key(Key::Control, Press);
-            let _ = enigo.key(Key::Unicode('a'), Click);
-            let _ = enigo.key(Key::Unicode('c'), Click);
-            let _ = enigo.key(Key::Control, Release);
-            //let _ = enigo.key(Key::Tab, Click);
-            thread::sleep(Duration::from_millis(150));
-            let _ = enigo.button(Button::Left, Click);
-            thread::sleep(Duration::from_millis(150));
+            let result = match copy_page_text(&mut input, &mut clipboard, loc_opentime) {
+                Ok(text) => text,
+                Err(e) => {
+                    println!("{}", e);
+                    tx.send(BotMessage::CopyFailed(e)).unwrap();
+                    continue 'main;
+                }
+            };
 
             // process text
-            let result = String::from("asd");
             tx.send(BotMessage::Copied(result.clone())).unwrap();
-            let trips: Vec<Trip> = re_opentime_trip
-                .captures_iter(&result)
-                .map(|c| c.extract())
-                .map(
-                    |(_, [id, date, days, rep, dep, arr, blk, crd, lay, prem])| Trip {
-                        id: id.to_owned(),
-                        date: date.parse().unwrap(),
-                        days: days.parse().unwrap(),
-                        report: rep.parse().unwrap(),
-                        depart: dep.parse().unwrap(),
-                        arrive: arr.parse().unwrap(),
-                        block: Time::from_num_str(blk).unwrap(),
-                        credit: Time::from_num_str(crd).unwrap(),
-                        layovers: lay.split_whitespace().map(|s| s.to_owned()).collect(),
-                        premium: !prem.is_empty(),
-                    },
-                )
-                .collect();
-
-            // apply filters
-            let filtered_trips: Vec<(BotAction, &str)> =
-                trips
-                    .iter()
-                    .map(|t| {
-                        (
-                            rules.iter().map(|r| r.get_action(t)).fold(
-                                BotAction::Nothing,
-                                |a, b| if b as u8 > a as u8 { b } else { a },
-                            ),
-                            t.id.as_str(),
-                        )
-                    })
-                    .collect();
-
-            // alert if any match
-            for t in &filtered_trips {
-                println!("{:?} {}", t.0, t.1);
-                if t.0 == BotAction::Pickup {
-                    add_trip_from_opentime(&mut enigo, t.1);
-                    sink.play();
+            ctx.today = Date::today();
+            let page = opentime::parse(&result, ctx.today);
+            for d in &page.diagnostics {
+                println!("{}", d);
+                tx.send(BotMessage::BadRow(d.clone())).unwrap();
+            }
+            let trips: Vec<Trip> = page.trips;
+
+            // apply filters, alert if any match
+            for (trip, t) in evaluate(&rules, trips.iter(), &ctx) {
+                println!("{:?} {}", t.action, t.trip);
+                tx.send(BotMessage::Evaluated(t.clone())).unwrap();
+                if t.action == BotAction::Pickup && dry_run {
+                    simulate_pickup(&tx, &t.trip);
+                } else if t.action == BotAction::Pickup {
+                    match add_trip_from_opentime(&mut input, &t.trip) {
+                        Ok(()) => record_pickup(&tx, &mut ctx.credit, trip),
+                        Err(e) => {
+                            println!("{}", e);
+                            tx.send(BotMessage::PickupFailed(t.trip.clone(), e))
+                                .unwrap();
+                        }
+                    }
+                    alarm.play();
                     state = AppState::Stopped;
                     tx.send(BotMessage::Stop).unwrap();
-                    continue;
-                } else if t.0 == BotAction::Alert {
+                    continue 'main;
+                } else if t.action == BotAction::Alert {
                     // alert user
-                    sink.play();
+                    alarm.play();
                     state = AppState::Alerting;
                     tx.send(BotMessage::TripFound).unwrap();
                 }
@@ -1119,47 +1897,797 @@ pub fn bot_thread(rx: Receiver<BotMessage>, tx: Sender<BotMessage>) {
     }
 }
 
-fn add_trip_from_otadd(enigo: &mut Enigo, trip_id: &str) -> enigo::InputResult<()> {
-    hit_button(enigo, trip_id)?;
-    hit_button(enigo, "it r")
+// records what add_trip_from_opentime would type and reports it instead of sending it
+fn simulate_pickup(tx: &Sender<BotMessage>, trip_id: &str) {
+    let mut input = RecordedInput::default();
+    let _ = add_trip_from_opentime(&mut input, trip_id);
+    tx.send(BotMessage::DryRunPickup(trip_id.to_owned(), input.events))
+        .unwrap();
 }
 
-fn add_trip_from_opentime(enigo: &mut Enigo, trip_id: &str) -> enigo::InputResult<()> {
-    hit_button(enigo, "submit")?;
-    thread::sleep(Duration::from_millis(1500)); // this delay needs to wait until the page has loaded
-    hit_button(enigo, "add")?;
-    thread::sleep(Duration::from_millis(1500)); // this delay needs to wait until the page has loaded
-    hit_button(enigo, trip_id)?;
-    thread::sleep(Duration::from_millis(50));
-    hit_button(enigo, "it r")
+fn add_trip_from_opentime(input: &mut impl Input, trip_id: &str) -> enigo::InputResult<()> {
+    hit_button(input, "submit")?;
+    input.wait(Duration::from_millis(1500)); // this delay needs to wait until the page has loaded
+    hit_button(input, "add")?;
+    input.wait(Duration::from_millis(1500)); // this delay needs to wait until the page has loaded
+    hit_button(input, trip_id)?;
+    input.wait(Duration::from_millis(50));
+    hit_button(input, "it r")
 }
 
 // these durations should be randomized if possible, should total to ~1 sec
-fn hit_button(enigo: &mut Enigo, button_name: &str) -> enigo::InputResult<()> {
+fn hit_button(input: &mut impl Input, button_name: &str) -> enigo::InputResult<()> {
     println!("hitting [{}] button", button_name);
 
     // open quick find bar
     println!("hitting /");
-    enigo.key(Key::Unicode('/'), Click)?;
-    thread::sleep(Duration::from_millis(28));
+    input.key(Key::Unicode('/'))?;
+    input.wait(Duration::from_millis(28));
 
     // type button name
     println!("hitting trip id");
-    enigo.text(button_name)?;
-    thread::sleep(Duration::from_millis(200));
+    input.text(button_name)?;
+    input.wait(Duration::from_millis(200));
 
     // navigate to button
     println!("hitting shoft+tab");
-    //let _ = enigo.key(Key::Tab, Click);
-    enigo.key(Key::Shift, Press)?;
-    enigo.key(Key::Tab, Click)?;
-    enigo.key(Key::Shift, Release)?;
-    thread::sleep(Duration::from_millis(75));
+    input.chord(&[Key::Shift], Key::Tab)?;
+    input.wait(Duration::from_millis(75));
 
     // click button
     println!("hitting enter");
-    enigo.key(Key::Return, Click)?;
-    thread::sleep(Duration::from_millis(5));
+    input.key(Key::Return)?;
+    input.wait(Duration::from_millis(5));
 
     Ok(())
 }
+
+// helpers for tests in any module
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+
+    // a trip from one line of board text, dated from the start of 2026
+    pub fn parse_trip(line: &str) -> Trip {
+        let today = Date {
+            year: 2026,
+            month: 1,
+            day: 1,
+        };
+        opentime::parse(line, today).trips.remove(0)
+    }
+
+    // a single rule that picks up any trip
+    pub fn pickup_everything() -> RuleSet {
+        RuleSet {
+            mode: MatchMode::FirstMatch,
+            ranked: false,
+            rules: vec![Rule {
+                name: "everything".to_owned(),
+                action: BotAction::Pickup,
+                ..Default::default()
+            }],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::pickup_everything;
+    use super::*;
+
+    fn fixture(name: &str) -> RgbaImage {
+        let path = format!("{}/tests/fixtures/{}.png", env!("CARGO_MANIFEST_DIR"), name);
+        xcap::image::open(&path)
+            .unwrap_or_else(|e| panic!("failed to open {path}: {e}"))
+            .to_rgba8()
+    }
+
+    fn prem_ys(screen: &RgbaImage, first_row: &OpentimeRow) -> Vec<u32> {
+        collect_prem(screen, first_row)
+            .iter()
+            .map(|r| r.y)
+            .collect()
+    }
+
+    const OPENTIME_ROW: OpentimeRow = OpentimeRow {
+        y: 168,
+        h: 18,
+        x_pairing: 42,
+        w_pairing: 64,
+        x_premium: 589,
+    };
+
+    #[test]
+    fn finds_first_row() {
+        let screen = fixture("opentime");
+        let row = find_first_row(&screen).unwrap();
+
+        assert_eq!(row, OPENTIME_ROW);
+        assert!(!table_moved(&screen, &row));
+        assert_eq!(prem_ys(&screen, &row), vec![188, 228, 248]);
+    }
+
+    #[test]
+    fn scrolled_page() {
+        let screen = fixture("scrolled");
+        assert!(table_moved(&screen, &OPENTIME_ROW));
+
+        let row = find_first_row(&screen).unwrap();
+        assert_eq!(
+            row,
+            OpentimeRow {
+                y: 123,
+                ..OPENTIME_ROW
+            }
+        );
+        assert!(!table_moved(&screen, &row));
+        assert_eq!(prem_ys(&screen, &row), vec![123, 163]);
+    }
+
+    #[test]
+    fn resized_window() {
+        let screen = fixture("resized");
+        assert!(table_moved(&screen, &OPENTIME_ROW));
+
+        let row = find_first_row(&screen).unwrap();
+        assert_eq!(
+            row,
+            OpentimeRow {
+                y: 168,
+                h: 18,
+                x_pairing: 14,
+                w_pairing: 60,
+                x_premium: 496,
+            }
+        );
+        assert_eq!(prem_ys(&screen, &row), vec![208]);
+    }
+
+    #[test]
+    fn missing_table() {
+        let screen = fixture("missing");
+        assert!(table_moved(&screen, &OPENTIME_ROW));
+        assert_eq!(find_first_row(&screen), None);
+    }
+
+    #[test]
+    fn clicked_links() {
+        let screen = fixture("clicked");
+        let row = find_first_row(&screen).unwrap();
+
+        assert_eq!(row, OPENTIME_ROW);
+        assert!(!table_moved(&screen, &row));
+        assert_eq!(prem_ys(&screen, &row), vec![188, 228, 248]);
+    }
+
+    #[test]
+    fn first_frame_always_moved() {
+        let screen = fixture("opentime");
+        let empty = OpentimeRow {
+            y: 0,
+            h: 0,
+            x_pairing: 0,
+            w_pairing: 0,
+            x_premium: 0,
+        };
+        assert!(table_moved(&screen, &empty));
+    }
+
+    #[test]
+    fn matches_rows_to_copied_text() {
+        let text = std::fs::read_to_string(format!(
+            "{}/tests/fixtures/replay/page.txt",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap();
+        let page = opentime::parse(&text, Date::default());
+
+        let screen = fixture("opentime");
+        let prem = collect_prem(&screen, &OPENTIME_ROW);
+        assert!(rows_match(&page, &OPENTIME_ROW, &prem));
+        let ids: Vec<&str> = prem
+            .iter()
+            .filter_map(|r| row_trip(&page, &OPENTIME_ROW, r))
+            .map(|t| t.id.as_str())
+            .collect();
+        assert_eq!(ids, ["W1030", "W1178", "W1203"]);
+
+        // scrolled down a row, so the screen's first row isn't the text's
+        let screen = fixture("scrolled");
+        let row = find_first_row(&screen).unwrap();
+        assert!(!rows_match(&page, &row, &collect_prem(&screen, &row)));
+        assert!(!rows_match(&page, &OPENTIME_ROW, &prem[1..]));
+    }
+
+    #[test]
+    fn ignored_rows_are_not_new() {
+        let screen = fixture("opentime");
+        let mut ignored = HashSet::new();
+        let prem = collect_prem(&screen, &OPENTIME_ROW);
+        prem.iter()
+            .for_each(|row| ignore_trip(&screen, row, &mut ignored));
+
+        assert!(prem.iter().all(|row| is_ignored(&screen, row, &ignored)));
+        assert!(!is_ignored(&screen, &OPENTIME_ROW, &ignored));
+    }
+
+    fn trip(days: u8, report: u32, depart: u32, arrive: u32, credit: u32) -> Trip {
+        Trip {
+            id: "A100".to_owned(),
+            date: Date::default(),
+            days,
+            report: Time::from_minutes(report),
+            depart: Time::from_minutes(depart),
+            arrive: Time::from_minutes(arrive),
+            block: Time::from_minutes(credit),
+            credit: Time::from_minutes(credit),
+            layovers: vec![],
+            premium: false,
+        }
+    }
+
+    #[test]
+    fn nested_groups() {
+        let mut t = trip(4, 6 * 60, 7 * 60, 12 * 60, 20 * 60);
+        t.layovers = vec!["DUB".to_owned()];
+        let ctx = EvalContext::default();
+        let layover = |code: &str| Filter::IncludeLayover(vec![code.to_owned()]);
+
+        // layover in DUB or EDI, but not a 4 day trip
+        let f = Filter::All(vec![
+            Filter::Any(vec![layover("DUB"), layover("EDI")]),
+            Filter::Not(vec![Filter::NumDays(Op::Eq, 4)]),
+        ]);
+        assert!(!f.eval(&t, &ctx));
+        t.days = 3;
+        assert!(f.eval(&t, &ctx));
+        t.layovers = vec!["LHR".to_owned()];
+        assert!(!f.eval(&t, &ctx));
+
+        // an empty All passes everything, an empty Any nothing
+        assert!(Filter::All(vec![]).eval(&t, &ctx));
+        assert!(!Filter::Any(vec![]).eval(&t, &ctx));
+        assert!(!Filter::Not(vec![Filter::All(vec![])]).eval(&t, &ctx));
+        assert!(Filter::Not(vec![Filter::Any(vec![])]).eval(&t, &ctx));
+
+        // Not over a group, and Not of several filters passes unless all of them do
+        let f = Filter::Not(vec![Filter::Any(vec![layover("DUB"), layover("LHR")])]);
+        assert!(!f.eval(&t, &ctx));
+        let f = Filter::Not(vec![layover("LHR"), Filter::NumDays(Op::Eq, 4)]);
+        assert!(f.eval(&t, &ctx));
+        t.days = 4;
+        assert!(!f.eval(&t, &ctx));
+    }
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(
+            "0630".parse(),
+            Ok(Time {
+                hours: 6,
+                minutes: 30
+            })
+        );
+        assert_eq!(
+            "630".parse(),
+            Ok(Time {
+                hours: 6,
+                minutes: 30
+            })
+        );
+        assert_eq!(
+            "23:59".parse(),
+            Ok(Time {
+                hours: 23,
+                minutes: 59
+            })
+        );
+        assert_eq!(
+            "10230".parse(),
+            Ok(Time {
+                hours: 102,
+                minutes: 30
+            })
+        );
+        assert_eq!(
+            "102:30".parse(),
+            Ok(Time {
+                hours: 102,
+                minutes: 30
+            })
+        );
+        for bad in [
+            "", "30", "0660", "07:2", "25h", "-100", "+0100", "12:345", "1٣00",
+        ] {
+            assert_eq!(bad.parse::<Time>(), Err(ParseTimeError), "{bad}");
+        }
+    }
+
+    #[test]
+    fn overnight_trip() {
+        // reports 22:00, departs 23:00, lands 05:15 the next morning
+        let t = trip(1, 22 * 60, 23 * 60, 5 * 60 + 15, 6 * 60);
+
+        assert_eq!(
+            t.span(Field::Report, Field::Arrive),
+            Time {
+                hours: 7,
+                minutes: 15
+            }
+        );
+        assert_eq!(
+            t.span(Field::Arrive, Field::Report),
+            Time {
+                hours: 7,
+                minutes: 15
+            }
+        );
+        assert!(
+            Filter::TimeDiff(
+                Field::Depart,
+                Field::Arrive,
+                Op::Lt,
+                Time {
+                    hours: 7,
+                    minutes: 0
+                }
+            )
+            .eval(&t, &EvalContext::default())
+        );
+    }
+
+    #[test]
+    fn multi_day_trip() {
+        // 4 days, landing at 09:05 on day 4
+        let t = trip(4, 13 * 60 + 15, 14 * 60 + 15, 9 * 60 + 5, 23 * 60 + 30);
+
+        assert_eq!(
+            t.span(Field::Report, Field::Arrive),
+            Time {
+                hours: 67,
+                minutes: 50
+            }
+        );
+    }
+
+    #[test]
+    fn mixed_times_never_pass() {
+        let t = trip(1, 6 * 60, 7 * 60, 12 * 60, 5 * 60);
+        let ctx = EvalContext::default();
+
+        // arrive is 12:00 and block 5:00, but one is a time of day and the other a length
+        let f = Filter::TimeDiff(Field::Arrive, Field::Block, Op::Gt, Time::default());
+        assert!(f.mixed_times().is_some());
+        assert!(!f.eval(&t, &ctx));
+        let f = Filter::FieldIs(Field::Report, Op::Lt, Time::from_minutes(30 * 60));
+        assert!(f.mixed_times().is_some());
+        assert!(!f.eval(&t, &ctx));
+
+        let f = Filter::TimeDiff(Field::Credit, Field::Block, Op::Eq, Time::default());
+        assert_eq!(f.mixed_times(), None);
+        assert!(f.eval(&t, &ctx));
+    }
+
+    #[test]
+    fn derived_fields() {
+        // 3 days, 11:00 of block and 18:00 of credit
+        let mut t = trip(3, 6 * 60, 7 * 60, 18 * 60 + 30, 18 * 60);
+        t.block = Time::from_minutes(11 * 60);
+        t.layovers = vec!["DUB".to_owned(), "EDI".to_owned()];
+
+        assert_eq!(t.get(Field::Duty), Time::from_minutes(60 * 60 + 30));
+        assert_eq!(t.get(Field::Sit), Time::from_minutes(49 * 60 + 30));
+        assert_eq!(t.get(Field::CreditPerDay), Time::from_minutes(6 * 60));
+        assert_eq!(t.end_date().to_string(), "JAN 03, 2025");
+
+        let ctx = EvalContext::default();
+        assert!(
+            Filter::FieldIs(Field::CreditPerDay, Op::GtEq, Time::from_minutes(5 * 60))
+                .eval(&t, &ctx)
+        );
+        assert!(Filter::NumLayovers(Op::Eq, 2).eval(&t, &ctx));
+        assert!(Filter::EndDateIs(Op::Eq, DateExpr::Today(2)).eval(&t, &ctx));
+        assert!(
+            Filter::TimeDiff(Field::Duty, Field::Block, Op::Eq, t.get(Field::Sit)).eval(&t, &ctx)
+        );
+    }
+
+    #[test]
+    fn pickup_keystrokes() {
+        let mut input = RecordedInput::default();
+        add_trip_from_opentime(&mut input, "W1030").unwrap();
+
+        let button = |name: &str| {
+            vec![
+                InputEvent::Chord(vec![], Key::Unicode('/')),
+                InputEvent::Wait(Duration::from_millis(28)),
+                InputEvent::Text(name.to_owned()),
+                InputEvent::Wait(Duration::from_millis(200)),
+                InputEvent::Chord(vec![Key::Shift], Key::Tab),
+                InputEvent::Wait(Duration::from_millis(75)),
+                InputEvent::Chord(vec![], Key::Return),
+                InputEvent::Wait(Duration::from_millis(5)),
+            ]
+        };
+        let page_load = InputEvent::Wait(Duration::from_millis(1500));
+        let expected = [
+            button("submit"),
+            vec![page_load.clone()],
+            button("add"),
+            vec![page_load],
+            button("W1030"),
+            vec![InputEvent::Wait(Duration::from_millis(50))],
+            button("it r"),
+        ]
+        .concat();
+        assert_eq!(input.events, expected);
+
+        let mut input = RecordedInput::default();
+        refresh_page(&mut input).unwrap();
+        assert_eq!(crate::input::plan(&input.events), "Control+r");
+    }
+
+    // hands back the page the browser would have copied
+    struct PageClipboard(String);
+
+    impl Clipboard for PageClipboard {
+        fn get_text(&mut self) -> Result<String, ClipboardError> {
+            Ok(self.0.clone())
+        }
+
+        fn set_text(&mut self, _: &str) -> Result<(), ClipboardError> {
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct QuietAlarm {
+        played: bool,
+    }
+
+    impl Alarm for QuietAlarm {
+        fn play(&mut self) {
+            self.played = true;
+        }
+
+        fn pause(&mut self) {}
+    }
+
+    #[test]
+    fn replays_a_session() {
+        let dir = format!("{}/tests/fixtures/replay", env!("CARGO_MANIFEST_DIR"));
+        let config = BotConfig {
+            updated_time_pos: (517, 179, 150, 40),
+            // nothing is refreshed during the replay
+            refresh_interval: (600, 601),
+            refresh: [87, 62, 20, 20],
+            credit_cap: None,
+            existing_credit: Time::default(),
+            airports: Airports::default(),
+            replay: Some(dir.clone()),
+        };
+        let mut capture = config.capture().unwrap();
+        let mut input = RecordedInput::default();
+        let mut clipboard =
+            PageClipboard(std::fs::read_to_string(format!("{}/page.txt", dir)).unwrap());
+        let mut alarm = QuietAlarm::default();
+        let rules = RuleSet {
+            mode: MatchMode::FirstMatch,
+            ranked: false,
+            rules: vec![Rule {
+                name: "dublin".to_owned(),
+                filters: vec![Filter::IncludeLayover(vec!["DUB".to_owned()])],
+                action: BotAction::Pickup,
+                ..Default::default()
+            }],
+        };
+
+        let (to_bot, rx) = std::sync::mpsc::channel();
+        let (tx, from_bot) = std::sync::mpsc::channel();
+        to_bot
+            .send(BotMessage::Start(rules, Schedule::default(), true))
+            .unwrap();
+        // with the app gone the bot returns once the replay has stopped it
+        drop(to_bot);
+        run_image_bot(
+            rx,
+            tx,
+            &config,
+            capture.as_mut(),
+            &mut input,
+            &mut clipboard,
+            &mut alarm,
+        );
+
+        let mut pickup = RecordedInput::default();
+        add_trip_from_opentime(&mut pickup, "W1030").unwrap();
+        let log: Vec<String> = from_bot
+            .try_iter()
+            .map(|m| match m {
+                BotMessage::Evaluated(t) => format!("{} {}", t.trip, t.action),
+                BotMessage::DryRunPickup(trip, events) => {
+                    assert_eq!(events, pickup.events);
+                    format!("dry run {}", trip)
+                }
+                m => format!("{:?}", m),
+            })
+            .collect();
+        assert_eq!(
+            log,
+            [
+                "W1030 Pickup",
+                "dry run W1030",
+                "W1178 Do Nothing",
+                "W1203 Do Nothing",
+                "ReplayFinished",
+                "Stop",
+            ]
+        );
+        // the page was copied once and nothing was picked up
+        assert_eq!(
+            crate::input::plan(&input.events),
+            "Control+a, Control+c, click at 592, 199, wait 150ms"
+        );
+        assert!(!alarm.played);
+    }
+
+    #[test]
+    fn ranks_by_points() {
+        let rule = |name: &str, filters, points, per_hour| Rule {
+            name: name.to_owned(),
+            filters,
+            action: BotAction::Pickup,
+            points,
+            per_hour,
+            ..Default::default()
+        };
+        let mut set = RuleSet {
+            mode: MatchMode::Priority,
+            ranked: true,
+            rules: vec![
+                rule("any", vec![], 1, None),
+                rule("credit", vec![], 2, Some(Field::CreditPerDay)),
+                rule("premium", vec![Filter::IsPrem], 10, None),
+            ],
+        };
+        let mut short = trip(1, 6 * 60, 7 * 60, 12 * 60, 4 * 60);
+        short.id = "A1".to_owned();
+        let mut long = trip(2, 6 * 60, 7 * 60, 12 * 60, 16 * 60);
+        long.id = "A2".to_owned();
+        let mut premium = short.clone();
+        premium.id = "A3".to_owned();
+        premium.premium = true;
+        let trips = [short, long, premium];
+        let ctx = EvalContext::default();
+
+        // 1 + 2 * 4, 1 + 2 * 8 and 1 + 2 * 4 + 10
+        let scores: Vec<i32> = trips.iter().map(|t| score(&set, t, &ctx)).collect();
+        assert_eq!(scores, [9, 17, 19]);
+        let order: Vec<&str> = evaluate(&set, trips.iter(), &ctx)
+            .iter()
+            .map(|(t, trace)| {
+                assert_eq!(trace.score, Some(score(&set, t, &ctx)));
+                t.id.as_str()
+            })
+            .collect();
+        assert_eq!(order, ["A3", "A2", "A1"]);
+
+        set.ranked = false;
+        let order: Vec<&str> = evaluate(&set, trips.iter(), &ctx)
+            .iter()
+            .map(|(t, _)| t.id.as_str())
+            .collect();
+        assert_eq!(order, ["A1", "A2", "A3"]);
+    }
+
+    #[test]
+    fn pickups_share_the_cap() {
+        let set = pickup_everything();
+        let mut first = trip(1, 6 * 60, 7 * 60, 12 * 60, 5 * 60);
+        first.id = "A1".to_owned();
+        let mut second = first.clone();
+        second.id = "A2".to_owned();
+        let mut ctx = EvalContext::default();
+        ctx.credit.cap = Some(Time::from_minutes(8 * 60));
+
+        // either fits under the cap on its own, but not both
+        let actions: Vec<BotAction> = evaluate(&set, [&first, &second].into_iter(), &ctx)
+            .iter()
+            .map(|(_, trace)| trace.action)
+            .collect();
+        assert_eq!(actions, [BotAction::Pickup, BotAction::Alert]);
+        assert!(ctx.credit.tally.picked_up.is_empty());
+    }
+
+    #[test]
+    fn pickups_share_the_schedule() {
+        let rule = |name: &str, filters, points| Rule {
+            name: name.to_owned(),
+            filters,
+            action: BotAction::Pickup,
+            points,
+            ..Default::default()
+        };
+        let set = RuleSet {
+            mode: MatchMode::FirstMatch,
+            ranked: true,
+            rules: vec![
+                rule("any", vec![], 1),
+                rule("premium", vec![Filter::IsPrem], 10),
+            ],
+        };
+        let mut overnight = trip(1, 20 * 60, 21 * 60, 2 * 60, 5 * 60);
+        overnight.id = "A1".to_owned();
+        // reports before the overnight trip is back, and ranks first
+        let mut premium = trip(1, 60, 2 * 60, 7 * 60, 5 * 60);
+        premium.id = "A2".to_owned();
+        premium.date = overnight.date.add_days(1);
+        premium.premium = true;
+        let mut later = premium.clone();
+        later.id = "A3".to_owned();
+        later.date = overnight.date.add_days(2);
+        later.premium = false;
+
+        let decided: Vec<(&str, BotAction)> = evaluate(
+            &set,
+            [&overnight, &premium, &later].into_iter(),
+            &EvalContext::default(),
+        )
+        .iter()
+        .map(|(t, trace)| (t.id.as_str(), trace.action))
+        .collect();
+        assert_eq!(
+            decided,
+            [
+                ("A2", BotAction::Pickup),
+                ("A1", BotAction::Alert),
+                ("A3", BotAction::Pickup),
+            ]
+        );
+    }
+
+    #[test]
+    fn date_expressions() {
+        let today = Date {
+            year: 2025,
+            month: 12,
+            day: 30,
+        };
+        let resolve = |s: &str| s.parse::<DateExpr>().map(|d| d.resolve(today).to_string());
+
+        assert_eq!(resolve("today"), Ok("DEC 30, 2025".to_owned()));
+        assert_eq!(resolve("Today + 3"), Ok("JAN 02, 2026".to_owned()));
+        assert_eq!(resolve("today-30"), Ok("NOV 30, 2025".to_owned()));
+        assert_eq!(resolve("2026-02-01"), Ok("FEB 01, 2026".to_owned()));
+        assert_eq!(resolve("todayish"), Err(ParseDateError));
+        assert_eq!(resolve("2026-02-30"), Err(ParseDateError));
+
+        // rules saved before relative dates still load
+        let old: Filter =
+            serde_json::from_str(r#"{"DateIs":["Gt",{"year":2025,"month":1,"day":5}]}"#).unwrap();
+        assert_eq!(
+            old,
+            Filter::DateIs(
+                Op::Gt,
+                DateExpr::On(Date {
+                    year: 2025,
+                    month: 1,
+                    day: 5
+                })
+            )
+        );
+        assert_eq!(
+            serde_json::to_string(&Filter::DateIs(Op::Eq, DateExpr::Today(3))).unwrap(),
+            r#"{"DateIs":["Eq",3]}"#
+        );
+    }
+
+    #[test]
+    fn date_range_filters() {
+        let on = |year, month, day| DateExpr::On(Date { year, month, day });
+        // Tue DEC 30, 2025 to Thu JAN 01, 2026
+        let mut t = trip(3, 0, 0, 0, 0);
+        t.date = Date {
+            year: 2025,
+            month: 12,
+            day: 30,
+        };
+
+        assert_eq!(t.end_date().to_string(), "JAN 01, 2026");
+        assert!(
+            Filter::DateBetween(on(2025, 12, 1), on(2026, 1, 31)).eval(&t, &EvalContext::default())
+        );
+        assert!(
+            !Filter::DateBetween(on(2026, 1, 1), on(2026, 1, 31)).eval(&t, &EvalContext::default())
+        );
+        assert!(
+            Filter::OnWeekday(vec![Weekday::Tue, Weekday::Sat]).eval(&t, &EvalContext::default())
+        );
+        assert!(
+            !Filter::OnWeekday(vec![Weekday::Fri, Weekday::Sat]).eval(&t, &EvalContext::default())
+        );
+        assert!(
+            Filter::Overlaps(vec![on(2025, 12, 25), on(2026, 1, 1)])
+                .eval(&t, &EvalContext::default())
+        );
+        assert!(
+            !Filter::Overlaps(vec![on(2025, 12, 29), on(2026, 1, 2)])
+                .eval(&t, &EvalContext::default())
+        );
+        assert!(Filter::EndsBefore(on(2026, 1, 2)).eval(&t, &EvalContext::default()));
+        assert!(!Filter::EndsBefore(on(2026, 1, 1)).eval(&t, &EvalContext::default()));
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn display_round_trips(minutes in 0u32..1000 * 60) {
+            let time = Time::from_minutes(minutes);
+            proptest::prop_assert_eq!(time.minutes(), minutes);
+            proptest::prop_assert_eq!(time.to_string().parse(), Ok(time));
+        }
+
+        #[test]
+        fn parse_never_panics(s in "\\PC{0,8}") {
+            let _ = s.parse::<Time>();
+        }
+
+        #[test]
+        fn clock_fields_are_ordered(
+            days in 1u8..8,
+            report in 0u32..1440,
+            depart in 0u32..1440,
+            arrive in 0u32..1440,
+        ) {
+            let t = trip(days, report, depart, arrive, 0);
+            let (r, d, a) = (t.offset(Field::Report), t.offset(Field::Depart), t.offset(Field::Arrive));
+            proptest::prop_assert!(r <= d && d <= a);
+            proptest::prop_assert!(d - r < 1440);
+            proptest::prop_assert_eq!(t.span(Field::Report, Field::Arrive), t.span(Field::Arrive, Field::Report));
+            proptest::prop_assert_eq!(t.span(Field::Report, Field::Arrive).minutes(), a - r);
+        }
+
+        #[test]
+        fn field_is_across_midnight(
+            days in 1u8..4,
+            report in 0u32..1440,
+            arrive in 0u32..1440,
+            value in 0u32..1440,
+        ) {
+            let t = trip(days, report, report, arrive, 0);
+            // both read as minutes since the report, wrapping at midnight
+            let since_report = |m: u32| (m + 1440 - report) % 1440;
+            for (op, expected) in [
+                (Op::Lt, since_report(arrive) < since_report(value)),
+                (Op::GtEq, since_report(arrive) >= since_report(value)),
+                (Op::Eq, arrive == value),
+            ] {
+                let f = Filter::FieldIs(Field::Arrive, op, Time::from_minutes(value));
+                proptest::prop_assert_eq!(f.eval(&t, &EvalContext::default()), expected);
+            }
+            let f = Filter::FieldIs(Field::Report, Op::Lt, Time::from_minutes(value));
+            proptest::prop_assert_eq!(f.eval(&t, &EvalContext::default()), report < value);
+        }
+
+        #[test]
+        fn time_diff_across_midnight(
+            report in 0u32..1440,
+            arrive in 0u32..1440,
+            value in 0u32..1440,
+        ) {
+            let t = trip(1, report, report, arrive, 0);
+            let duty = (arrive + 1440 - report) % 1440;
+            for (op, expected) in [(Op::LtEq, duty <= value), (Op::Gt, duty > value)] {
+                let f = Filter::TimeDiff(Field::Arrive, Field::Report, op, Time::from_minutes(value));
+                proptest::prop_assert_eq!(f.eval(&t, &EvalContext::default()), expected);
+            }
+        }
+
+        #[test]
+        fn credit_past_100_hours(credit in 0u32..200 * 60, limit in 0u32..200 * 60) {
+            let t = trip(1, 0, 0, 0, credit);
+            let f = Filter::FieldIs(Field::Credit, Op::GtEq, Time::from_minutes(limit));
+            proptest::prop_assert_eq!(f.eval(&t, &EvalContext::default()), credit >= limit);
+        }
+    }
+}
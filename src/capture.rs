@@ -0,0 +1,169 @@
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+use xcap::image::{RgbaImage, imageops};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CaptureError {
+    Unavailable(String),
+    // a configured area starts left of or above the screen
+    OffScreen(i32, i32),
+    // a replay has shown its last frame
+    Finished,
+}
+
+impl Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureError::Unavailable(e) => write!(f, "screen capture unavailable: {}", e),
+            CaptureError::OffScreen(x, y) => write!(f, "{}, {} is off the screen", x, y),
+            CaptureError::Finished => write!(f, "replay has no more frames"),
+        }
+    }
+}
+
+// where the bot gets its view of the screen from
+pub trait Capture {
+    fn frame(&mut self) -> Result<RgbaImage, CaptureError>;
+
+    // part of a frame, clipped to the frame's edges
+    fn area(&mut self, x: u32, y: u32, w: u32, h: u32) -> Result<RgbaImage, CaptureError> {
+        Ok(imageops::crop_imm(&self.frame()?, x, y, w, h).to_image())
+    }
+}
+
+// the first monitor
+pub struct LiveCapture(xcap::Monitor);
+
+impl LiveCapture {
+    pub fn new() -> Result<Self, CaptureError> {
+        xcap::Monitor::all()
+            .map_err(|e| CaptureError::Unavailable(e.to_string()))?
+            .into_iter()
+            .next()
+            .map(LiveCapture)
+            .ok_or(CaptureError::Unavailable("no monitors".to_owned()))
+    }
+}
+
+impl Capture for LiveCapture {
+    fn frame(&mut self) -> Result<RgbaImage, CaptureError> {
+        self.0
+            .capture_image()
+            .map_err(|e| CaptureError::Unavailable(e.to_string()))
+    }
+
+    fn area(&mut self, x: u32, y: u32, w: u32, h: u32) -> Result<RgbaImage, CaptureError> {
+        self.0
+            .capture_region(x, y, w, h)
+            .map_err(|e| CaptureError::Unavailable(e.to_string()))
+    }
+}
+
+// a recorded session, <timestamp>.png files played back one per frame in timestamp order,
+// areas are cut from the frame being shown so checking part of the screen doesn't skip ahead
+#[derive(Debug)]
+pub struct ReplayCapture {
+    frames: Vec<PathBuf>,
+    next: usize,
+    current: Option<RgbaImage>,
+}
+
+impl ReplayCapture {
+    // other files in the directory are left alone
+    pub fn open(dir: &Path) -> Result<Self, CaptureError> {
+        let mut frames: Vec<(u64, PathBuf)> = fs::read_dir(dir)
+            .map_err(|e| CaptureError::Unavailable(format!("{}: {}", dir.display(), e)))?
+            .filter_map(|e| {
+                let path = e.ok()?.path();
+                if path.extension()? != "png" {
+                    return None;
+                }
+                let timestamp = path.file_stem()?.to_str()?.parse().ok()?;
+                Some((timestamp, path))
+            })
+            .collect();
+        if frames.is_empty() {
+            return Err(CaptureError::Unavailable(format!(
+                "no <timestamp>.png frames in {}",
+                dir.display()
+            )));
+        }
+        frames.sort();
+
+        Ok(ReplayCapture {
+            frames: frames.into_iter().map(|(_, path)| path).collect(),
+            next: 0,
+            current: None,
+        })
+    }
+
+    fn load(&self, index: usize) -> Result<RgbaImage, CaptureError> {
+        let path = self.frames.get(index).ok_or(CaptureError::Finished)?;
+        xcap::image::open(path)
+            .map(|i| i.to_rgba8())
+            .map_err(|e| CaptureError::Unavailable(format!("{}: {}", path.display(), e)))
+    }
+}
+
+impl Capture for ReplayCapture {
+    fn frame(&mut self) -> Result<RgbaImage, CaptureError> {
+        let frame = self.load(self.next)?;
+        self.next += 1;
+        self.current = Some(frame.clone());
+        Ok(frame)
+    }
+
+    // before the first frame is played this is the first frame
+    fn area(&mut self, x: u32, y: u32, w: u32, h: u32) -> Result<RgbaImage, CaptureError> {
+        let current = match &self.current {
+            Some(current) => current,
+            None => self.current.insert(self.load(self.next)?),
+        };
+        Ok(imageops::crop_imm(current, x, y, w, h).to_image())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xcap::image::Rgba;
+
+    #[test]
+    fn replays_frames_in_order() {
+        let dir = std::env::temp_dir().join("hungrychicken-replay-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        // 900 sorts before 1000 by timestamp but not by name
+        for (name, shade) in [("1000.png", 20), ("900.png", 10), ("2000.png", 30)] {
+            RgbaImage::from_pixel(4, 3, Rgba([shade, shade, shade, 255]))
+                .save(dir.join(name))
+                .unwrap();
+        }
+        fs::write(dir.join("notes.txt"), "not a frame").unwrap();
+
+        let mut replay = ReplayCapture::open(&dir).unwrap();
+        let shades: Vec<u8> = (0..3).map(|_| replay.frame().unwrap()[(0, 0)][0]).collect();
+        assert_eq!(shades, [10, 20, 30]);
+        assert_eq!(replay.frame(), Err(CaptureError::Finished));
+
+        // areas stay on the current frame
+        let mut replay = ReplayCapture::open(&dir).unwrap();
+        let area = replay.area(1, 1, 2, 5).unwrap();
+        assert_eq!(area.dimensions(), (2, 2));
+        assert_eq!(area[(0, 0)][0], 10);
+        assert_eq!(replay.frame().unwrap()[(0, 0)][0], 10);
+        assert_eq!(replay.frame().unwrap()[(0, 0)][0], 20);
+        assert_eq!(replay.area(0, 0, 1, 1).unwrap()[(0, 0)][0], 20);
+        assert_eq!(replay.area(0, 0, 1, 1).unwrap()[(0, 0)][0], 20);
+        assert_eq!(replay.frame().unwrap()[(0, 0)][0], 30);
+
+        let _ = fs::remove_dir_all(&dir);
+        assert!(matches!(
+            ReplayCapture::open(&dir),
+            Err(CaptureError::Unavailable(_))
+        ));
+    }
+}
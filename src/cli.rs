@@ -0,0 +1,202 @@
+use crate::{
+    bot::{BotConfig, Date, EvalContext, RuleSet, Trip, decide},
+    opentime,
+    rules_file::load_rules,
+    schedule::Schedule,
+};
+use std::fs;
+
+// the release build is a windows subsystem app, so on windows redirect the output to a file
+// e.g. `hungrychicken eval --rules rules.json --input page.txt > result.txt`
+// --today sets the date the page was saved on, so year-less dates resolve the same way later
+// --config reads the home country and airport groups the bot would use
+const USAGE: &str = "usage: hungrychicken eval --rules <rules.json> --input <page.txt> \
+                     [--today <YYYY-MM-DD>] [--schedule <schedule.json>] [--config <config.json>]";
+
+// returns the process exit code
+pub fn eval(args: &[String]) -> i32 {
+    let mut rules_path = None;
+    let mut input_path = None;
+    let mut today = Date::today();
+    let mut schedule_path = None;
+    let mut config_path = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rules" => rules_path = args.next(),
+            "--input" => input_path = args.next(),
+            "--schedule" => schedule_path = args.next(),
+            "--config" => config_path = args.next(),
+            "--today" => match args.next().map(|s| s.parse()) {
+                Some(Ok(date)) => today = date,
+                _ => {
+                    eprintln!("--today needs a date like 2026-01-05\n{}", USAGE);
+                    return 2;
+                }
+            },
+            _ => {
+                eprintln!("unknown argument \"{}\"\n{}", arg, USAGE);
+                return 2;
+            }
+        }
+    }
+    let (Some(rules_path), Some(input_path)) = (rules_path, input_path) else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+
+    let rules = match load_rules(rules_path) {
+        Ok(rules) => rules,
+        Err(e) => {
+            eprintln!("{}: {}", rules_path, e);
+            return 1;
+        }
+    };
+    let text = match fs::read_to_string(input_path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("{}: could not read input: {}", input_path, e);
+            return 1;
+        }
+    };
+
+    let schedule = match schedule_path.map(|p| Schedule::load(p)) {
+        Some(Ok(schedule)) => schedule,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+        None => Schedule::default(),
+    };
+
+    let config = config_path.map(|p| {
+        fs::read_to_string(p)
+            .map_err(|e| e.to_string())
+            .and_then(|data| serde_json::from_str::<BotConfig>(&data).map_err(|e| e.to_string()))
+            .map_err(|e| format!("{}: could not read config: {}", p, e))
+    });
+    let airports = match config {
+        Some(Ok(config)) => config.airports,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+        None => Default::default(),
+    };
+
+    let page = opentime::parse(&text, today);
+    for d in &page.diagnostics {
+        eprintln!("{}: {}", input_path, d);
+    }
+    let ctx = EvalContext {
+        today,
+        schedule,
+        airports,
+        ..EvalContext::default()
+    };
+    print!("{}", action_table(&rules, &page.trips, &ctx));
+
+    0
+}
+
+fn action_table(rules: &RuleSet, trips: &[Trip], ctx: &EvalContext) -> String {
+    let rows: Vec<[String; 3]> = trips
+        .iter()
+        .map(|t| {
+            let (action, rule) = decide(rules, t, ctx);
+            [
+                t.id.clone(),
+                action.to_string(),
+                rule.map_or("-".to_owned(), |r| r.name.clone()),
+            ]
+        })
+        .collect();
+
+    let header = ["TRIP", "ACTION", "RULE"].map(|s| s.to_owned());
+    let widths: Vec<usize> = (0..2)
+        .map(|i| {
+            rows.iter()
+                .chain([&header])
+                .map(|r| r[i].len())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    [&header]
+        .into_iter()
+        .chain(rows.iter())
+        .map(|[id, action, rule]| {
+            format!(
+                "{:id_w$}  {:action_w$}  {}\n",
+                id,
+                action,
+                rule,
+                id_w = widths[0],
+                action_w = widths[1]
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{BotAction, Filter, MatchMode, Rule};
+
+    #[test]
+    fn prints_winning_rule() {
+        let page = opentime::parse(
+            "W1030 05JAN 3 0600 0700 1830 1450 1715 DUB EDI X\n\
+             W1102 06JAN 4 1315 1415 0905 2210 2330 LHR\n\
+             W1178 07JAN 2 2200 2300 0745 0915 1020 CDG X\n",
+            Date::default(),
+        );
+        let mut rules = RuleSet {
+            mode: MatchMode::Priority,
+            ranked: false,
+            rules: vec![
+                Rule {
+                    name: "premium".to_owned(),
+                    filters: vec![Filter::IsPrem],
+                    ..Default::default()
+                },
+                Rule {
+                    name: "dublin".to_owned(),
+                    filters: vec![Filter::IncludeLayover(vec!["DUB".to_owned()])],
+                    action: BotAction::Pickup,
+                    priority: 1,
+                    ..Default::default()
+                },
+            ],
+        };
+
+        assert_eq!(
+            action_table(&rules, &page.trips, &EvalContext::default()),
+            "TRIP   ACTION      RULE\n\
+             W1030  Pickup      dublin\n\
+             W1102  Do Nothing  -\n\
+             W1178  Alert       premium\n"
+        );
+
+        rules.rules[1].enabled = false;
+        assert_eq!(
+            action_table(&rules, &page.trips, &EvalContext::default()),
+            "TRIP   ACTION      RULE\n\
+             W1030  Alert       premium\n\
+             W1102  Do Nothing  -\n\
+             W1178  Alert       premium\n"
+        );
+
+        rules.rules[1].enabled = true;
+        rules.mode = MatchMode::FirstMatch;
+        assert_eq!(
+            action_table(&rules, &page.trips, &EvalContext::default()),
+            "TRIP   ACTION      RULE\n\
+             W1030  Alert       premium\n\
+             W1102  Do Nothing  -\n\
+             W1178  Alert       premium\n"
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    fmt::Display,
+    thread,
+    time::{Duration, Instant},
+};
+
+// written before copying so a copy that never lands can be told apart from an old one
+const SENTINEL: &str = "hungrychicken: waiting for copy";
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ClipboardError {
+    Unavailable(String),
+    Empty,
+    Stale,
+    // the keys or clicks for the copy couldn't be sent
+    Input(String),
+}
+
+impl Display for ClipboardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClipboardError::Unavailable(e) => write!(f, "clipboard unavailable: {}", e),
+            ClipboardError::Empty => write!(f, "clipboard is empty, nothing was copied"),
+            ClipboardError::Stale => write!(f, "clipboard was not updated by the copy"),
+            ClipboardError::Input(e) => write!(f, "input could not be sent: {}", e),
+        }
+    }
+}
+
+pub trait Clipboard {
+    fn get_text(&mut self) -> Result<String, ClipboardError>;
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError>;
+}
+
+pub struct SystemClipboard(arboard::Clipboard);
+
+impl SystemClipboard {
+    pub fn new() -> Result<Self, ClipboardError> {
+        arboard::Clipboard::new()
+            .map(SystemClipboard)
+            .map_err(|e| ClipboardError::Unavailable(e.to_string()))
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn get_text(&mut self) -> Result<String, ClipboardError> {
+        match self.0.get_text() {
+            Ok(s) => Ok(s),
+            Err(arboard::Error::ContentNotAvailable) => Err(ClipboardError::Empty),
+            Err(e) => Err(ClipboardError::Unavailable(e.to_string())),
+        }
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+        self.0
+            .set_text(text)
+            .map_err(|e| ClipboardError::Unavailable(e.to_string()))
+    }
+}
+
+// runs `copy` and waits up to `timeout` for it to replace the clipboard contents
+pub fn copy_text(
+    clipboard: &mut impl Clipboard,
+    copy: impl FnOnce() -> Result<(), ClipboardError>,
+    timeout: Duration,
+) -> Result<String, ClipboardError> {
+    clipboard.set_text(SENTINEL)?;
+    copy()?;
+
+    let start = Instant::now();
+    loop {
+        match clipboard.get_text() {
+            Ok(s) if s == SENTINEL => {}
+            Ok(s) if s.trim().is_empty() => return Err(ClipboardError::Empty),
+            Ok(s) => return Ok(s),
+            Err(ClipboardError::Empty) => {}
+            Err(e) => return Err(e),
+        }
+
+        if start.elapsed() > timeout {
+            return match clipboard.get_text() {
+                Ok(s) if s == SENTINEL => Err(ClipboardError::Stale),
+                _ => Err(ClipboardError::Empty),
+            };
+        }
+        thread::sleep(Duration::from_millis(25));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    // stands in for the X11/Wayland selection owner when there is no display
+    #[derive(Default, Clone)]
+    struct MemoryClipboard(Rc<RefCell<Option<String>>>);
+
+    impl Clipboard for MemoryClipboard {
+        fn get_text(&mut self) -> Result<String, ClipboardError> {
+            self.0.borrow().clone().ok_or(ClipboardError::Empty)
+        }
+
+        fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+            self.0.replace(Some(text.to_owned()));
+            Ok(())
+        }
+    }
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    fn copy_with(
+        clipboard: &mut MemoryClipboard,
+        copied: Option<&str>,
+    ) -> Result<String, ClipboardError> {
+        let browser = clipboard.clone();
+        copy_text(
+            clipboard,
+            move || {
+                if let Some(text) = copied {
+                    browser.0.replace(Some(text.to_owned()));
+                }
+                Ok(())
+            },
+            TIMEOUT,
+        )
+    }
+
+    #[test]
+    fn copies_page_text() {
+        let mut clipboard = MemoryClipboard::default();
+        clipboard.set_text("yesterday's page").unwrap();
+
+        assert_eq!(
+            copy_with(&mut clipboard, Some("today's page")),
+            Ok("today's page".to_owned())
+        );
+    }
+
+    #[test]
+    fn empty_copy() {
+        let mut clipboard = MemoryClipboard::default();
+
+        assert_eq!(
+            copy_with(&mut clipboard, Some("")),
+            Err(ClipboardError::Empty)
+        );
+        assert_eq!(
+            copy_with(&mut clipboard, Some(" \n ")),
+            Err(ClipboardError::Empty)
+        );
+    }
+
+    #[test]
+    fn stale_copy() {
+        let mut clipboard = MemoryClipboard::default();
+        clipboard.set_text("yesterday's page").unwrap();
+
+        assert_eq!(copy_with(&mut clipboard, None), Err(ClipboardError::Stale));
+    }
+
+    #[test]
+    fn failed_copy_keys() {
+        let mut clipboard = MemoryClipboard::default();
+        let failed = || Err(ClipboardError::Input("no display".into()));
+
+        // reported as it is, not as a clipboard that never changed
+        assert_eq!(
+            copy_text(&mut clipboard, failed, TIMEOUT),
+            Err(ClipboardError::Input("no display".into()))
+        );
+    }
+
+    #[test]
+    #[ignore = "needs an X11 or Wayland display, run under xvfb-run"]
+    fn system_clipboard_round_trip() {
+        let mut clipboard = SystemClipboard::new().unwrap();
+        clipboard.set_text("A1234 05JAN 2 0600").unwrap();
+
+        assert_eq!(clipboard.get_text(), Ok("A1234 05JAN 2 0600".to_owned()));
+    }
+}
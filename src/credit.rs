@@ -0,0 +1,178 @@
+use crate::bot::{Date, Time, Trip};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, fs, io};
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PickedUp {
+    pub id: String,
+    pub date: Date,
+    pub credit: Time,
+}
+
+// trips the bot has picked up, saved to credit.json so the tally survives a restart
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CreditTally {
+    pub picked_up: Vec<PickedUp>,
+}
+
+impl CreditTally {
+    // no file yet is an empty tally, one that can't be read is an error so it isn't replaced
+    pub fn load(path: &str) -> Result<Self, CreditError> {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(CreditTally::default()),
+            Err(e) => return Err(CreditError::Read(e.to_string())),
+        };
+        serde_json::from_str(&data).map_err(|e| CreditError::Parse(e.to_string()))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), CreditError> {
+        let js =
+            serde_json::to_string_pretty(self).map_err(|e| CreditError::Save(e.to_string()))?;
+        fs::write(path, js).map_err(|e| CreditError::Save(e.to_string()))
+    }
+
+    pub fn record(&mut self, trip: &Trip) {
+        if self
+            .picked_up
+            .iter()
+            .any(|p| p.id == trip.id && p.date == trip.date)
+        {
+            return;
+        }
+        self.picked_up.push(PickedUp {
+            id: trip.id.clone(),
+            date: trip.date,
+            credit: trip.credit,
+        });
+    }
+
+    // trips count towards the month they start in
+    pub fn month_total(&self, year: u16, month: u8) -> Time {
+        Time::from_minutes(
+            self.picked_up
+                .iter()
+                .filter(|p| p.date.year == year && p.date.month == month)
+                .map(|p| p.credit.minutes())
+                .sum(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CreditBudget {
+    // none for no limit
+    pub cap: Option<Time>,
+    // already on the schedule for the current month
+    pub existing: Time,
+    pub tally: CreditTally,
+    // the saved tally couldn't be read, so what's been picked up this month isn't known
+    pub tally_unknown: bool,
+}
+
+impl CreditBudget {
+    // credit in the trip's month before it is picked up
+    pub fn used(&self, trip: &Trip, today: Date) -> Time {
+        let mut used = self
+            .tally
+            .month_total(trip.date.year, trip.date.month)
+            .minutes();
+        if (trip.date.year, trip.date.month) == (today.year, today.month) {
+            used += self.existing.minutes();
+        }
+        Time::from_minutes(used)
+    }
+
+    // nothing is allowed while the tally is unknown, a pickup would be saved over it
+    pub fn allows(&self, trip: &Trip, today: Date) -> bool {
+        !self.tally_unknown
+            && self.cap.is_none_or(|cap| {
+                self.used(trip, today).minutes() + trip.credit.minutes() <= cap.minutes()
+            })
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CreditError {
+    Read(String),
+    Parse(String),
+    Save(String),
+}
+
+impl Display for CreditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CreditError::Read(e) => write!(f, "could not read credit tally: {}", e),
+            CreditError::Parse(e) => write!(f, "could not parse credit tally: {}", e),
+            CreditError::Save(e) => write!(f, "could not save credit tally: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bot::{
+            BotAction, EvalContext, decide,
+            test_support::{parse_trip, pickup_everything},
+        },
+        opentime,
+    };
+
+    const TODAY: Date = Date {
+        year: 2026,
+        month: 1,
+        day: 1,
+    };
+
+    #[test]
+    fn pickups_stop_at_the_cap() {
+        let page = opentime::parse(
+            "A1 05JAN 3 0600 0700 1800 1500 1800\n\
+             A2 12JAN 3 0600 0700 1800 1500 1800\n\
+             A3 03FEB 3 0600 0700 1800 1500 1800\n",
+            TODAY,
+        );
+        let rules = pickup_everything();
+        let mut ctx = EvalContext {
+            today: TODAY,
+            ..EvalContext::default()
+        };
+        ctx.credit.cap = Some(Time::from_minutes(75 * 60));
+        ctx.credit.existing = Time::from_minutes(40 * 60);
+
+        assert_eq!(decide(&rules, &page.trips[0], &ctx).0, BotAction::Pickup);
+        ctx.credit.tally.record(&page.trips[0]);
+        ctx.credit.tally.record(&page.trips[0]);
+        assert_eq!(
+            ctx.credit.used(&page.trips[1], TODAY),
+            Time::from_minutes(58 * 60)
+        );
+
+        // 58 + 18 is over 75, but February starts from nothing
+        assert_eq!(decide(&rules, &page.trips[1], &ctx).0, BotAction::Alert);
+        assert_eq!(decide(&rules, &page.trips[2], &ctx).0, BotAction::Pickup);
+        ctx.credit.tally_unknown = true;
+        assert_eq!(decide(&rules, &page.trips[2], &ctx).0, BotAction::Alert);
+    }
+
+    #[test]
+    fn tally_round_trips() {
+        let mut tally = CreditTally::default();
+        tally.record(&parse_trip("A1 05JAN 3 0600 0700 1800 1500 1800"));
+
+        let path = std::env::temp_dir().join("hungrychicken-credit-test.json");
+        let path = path.to_str().unwrap();
+        tally.save(path).unwrap();
+        assert_eq!(CreditTally::load(path).unwrap(), tally);
+
+        fs::write(path, "{\"picked_up\": [").unwrap();
+        assert!(matches!(
+            CreditTally::load(path),
+            Err(CreditError::Parse(_))
+        ));
+        let _ = fs::remove_file(path);
+        assert_eq!(CreditTally::load(path).unwrap(), CreditTally::default());
+    }
+}
@@ -0,0 +1,707 @@
+// the text form of rules, an alternative to building them filter by filter in the gui
+//
+//   mode priority
+//   alert "late reports" when report >= 10:00 and days <= 2 and not layover LHR
+//   pickup "weekends" priority 3 when weekday in sat, sun and (premium or credit >= 18:00)
+//
+// a rule is an action, a quoted name, then optionally "priority N", "disabled", "notes",
+// "points N", "per <field>" and "when" followed by filters. "ranked" after the mode sorts
+// matching trips by their points. the filters after "when" are the rule's top level list,
+// "or" binds looser than "and", and Filter::as_string prints each filter the way it's parsed
+use crate::bot::{BotAction, DateExpr, Field, Filter, MatchMode, Op, Rule, RuleSet, Time, Weekday};
+use std::{fmt::Display, ops::Range};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DslError {
+    // byte range of the offending text
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl DslError {
+    fn new(span: Range<usize>, message: impl Into<String>) -> Self {
+        DslError {
+            span,
+            message: message.into(),
+        }
+    }
+
+    // 1 based line and column of the start of the span
+    pub fn position(&self, text: &str) -> (usize, usize) {
+        let before = &text[..self.span.start.min(text.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        (line, column)
+    }
+
+    // the message with where it happened, for showing next to the text
+    pub fn describe(&self, text: &str) -> String {
+        let (line, column) = self.position(text);
+        format!("line {}, column {}: {}", line, column, self.message)
+    }
+}
+
+impl Display for DslError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    // keywords, fields, times, dates, numbers and codes
+    Word(String),
+    Str(String),
+    Op(Op),
+    Minus,
+    Comma,
+    Open,
+    Close,
+}
+
+impl Display for Tok {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tok::Word(w) => write!(f, "\"{}\"", w),
+            Tok::Str(s) => write!(f, "\"{}\"", s),
+            Tok::Op(op) => write!(f, "\"{}\"", op),
+            Tok::Minus => write!(f, "\"-\""),
+            Tok::Comma => write!(f, "\",\""),
+            Tok::Open => write!(f, "\"(\""),
+            Tok::Close => write!(f, "\")\""),
+        }
+    }
+}
+
+type Token = (Tok, Range<usize>);
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | ':' | '+' | '-' | '.')
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, DslError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let single = |tok| (tok, start..start + c.len_utf8());
+        match c {
+            c if c.is_whitespace() => {}
+            // comments run to the end of the line
+            '#' => while chars.next_if(|(_, c)| *c != '\n').is_some() {},
+            '(' => tokens.push(single(Tok::Open)),
+            ')' => tokens.push(single(Tok::Close)),
+            ',' => tokens.push(single(Tok::Comma)),
+            '-' => tokens.push(single(Tok::Minus)),
+            '=' | '!' | '<' | '>' => {
+                let eq = chars.next_if(|(_, c)| *c == '=').is_some();
+                let op = match (c, eq) {
+                    ('=', _) => Op::Eq,
+                    ('!', true) => Op::NEq,
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::LtEq,
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::GtEq,
+                    _ => return Err(DslError::new(start..start + 1, "expected \"!=\"")),
+                };
+                tokens.push((Tok::Op(op), start..start + if eq { 2 } else { 1 }));
+            }
+            '"' => {
+                let mut s = String::new();
+                let end = loop {
+                    match chars.next() {
+                        Some((i, '"')) => break i + 1,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c)) => s.push(c),
+                            None => break text.len(),
+                        },
+                        Some((_, c)) => s.push(c),
+                        None => {
+                            return Err(DslError::new(start..text.len(), "unterminated string"));
+                        }
+                    }
+                };
+                tokens.push((Tok::Str(s), start..end));
+            }
+            c if is_word_char(c) => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = chars.next_if(|(_, c)| is_word_char(*c)) {
+                    end = i + c.len_utf8();
+                }
+                tokens.push((Tok::Word(text[start..end].to_owned()), start..end));
+            }
+            c => {
+                return Err(DslError::new(
+                    start..start + c.len_utf8(),
+                    format!("unexpected \"{}\"", c),
+                ));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    // length of the text, where errors about running out of text point
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<Token, DslError> {
+        let token =
+            self.tokens.get(self.pos).cloned().ok_or_else(|| {
+                DslError::new(self.end..self.end, format!("expected {}", expected))
+            })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn unexpected(&self, expected: &str) -> DslError {
+        match self.peek() {
+            Some((tok, span)) => DslError::new(
+                span.clone(),
+                format!("expected {}, found {}", expected, tok),
+            ),
+            None => DslError::new(self.end..self.end, format!("expected {}", expected)),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some((Tok::Word(w), _)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat(&mut self, tok: Tok) -> bool {
+        let found = self.peek().is_some_and(|(t, _)| *t == tok);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), DslError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("\"{}\"", keyword)))
+        }
+    }
+
+    fn word(&mut self, expected: &str) -> Result<(String, Range<usize>), DslError> {
+        match self.next(expected)? {
+            (Tok::Word(w), span) => Ok((w, span)),
+            (tok, span) => Err(DslError::new(
+                span,
+                format!("expected {}, found {}", expected, tok),
+            )),
+        }
+    }
+
+    // a bare word or a quoted string
+    fn code(&mut self, expected: &str) -> Result<String, DslError> {
+        match self.next(expected)? {
+            (Tok::Word(s) | Tok::Str(s), _) => Ok(s),
+            (tok, span) => Err(DslError::new(
+                span,
+                format!("expected {}, found {}", expected, tok),
+            )),
+        }
+    }
+
+    // LHR, DUB, "West Coast" or none for an empty list
+    fn codes(&mut self) -> Result<Vec<String>, DslError> {
+        if self.eat_keyword("none") {
+            return Ok(vec![]);
+        }
+        let mut codes = vec![self.code("an airport code or group")?];
+        while self.eat(Tok::Comma) {
+            codes.push(self.code("an airport code or group")?);
+        }
+        Ok(codes)
+    }
+
+    fn value<T: std::str::FromStr>(&mut self, expected: &str) -> Result<T, DslError> {
+        let (w, span) = self.word(expected)?;
+        w.parse()
+            .map_err(|_| DslError::new(span, format!("expected {}, found \"{}\"", expected, w)))
+    }
+
+    fn op(&mut self) -> Result<Op, DslError> {
+        match self.next("a comparison")? {
+            (Tok::Op(op), _) => Ok(op),
+            (tok, span) => Err(DslError::new(
+                span,
+                format!("expected a comparison like >=, found {}", tok),
+            )),
+        }
+    }
+
+    fn field(&self) -> Option<Field> {
+        let Some((Tok::Word(w), _)) = self.peek() else {
+            return None;
+        };
+        Field::ALL
+            .into_iter()
+            .find(|f| w.eq_ignore_ascii_case(f.keyword()))
+    }
+
+    fn weekday(&self) -> Option<Weekday> {
+        let Some((Tok::Word(w), _)) = self.peek() else {
+            return None;
+        };
+        let w = w.to_lowercase();
+        [
+            ("monday", Weekday::Mon),
+            ("tuesday", Weekday::Tue),
+            ("wednesday", Weekday::Wed),
+            ("thursday", Weekday::Thu),
+            ("friday", Weekday::Fri),
+            ("saturday", Weekday::Sat),
+            ("sunday", Weekday::Sun),
+        ]
+        .into_iter()
+        .find(|(name, _)| w.len() >= 3 && name.starts_with(&w))
+        .map(|(_, day)| day)
+    }
+
+    // an empty list is allowed, it just never matches
+    fn list<T>(
+        &mut self,
+        item: impl Fn(&Self) -> Option<T>,
+        expected: &str,
+    ) -> Result<Vec<T>, DslError> {
+        let mut items = Vec::new();
+        while let Some(i) = item(self) {
+            items.push(i);
+            self.pos += 1;
+            if !self.eat(Tok::Comma) {
+                return Ok(items);
+            }
+            if item(self).is_none() {
+                return Err(self.unexpected(expected));
+            }
+        }
+        Ok(items)
+    }
+
+    fn rule_set(&mut self) -> Result<RuleSet, DslError> {
+        let mut set = RuleSet::default();
+        if self.eat_keyword("mode") {
+            set.mode = match self.word("a mode")? {
+                (w, _) if w.eq_ignore_ascii_case("priority") => MatchMode::Priority,
+                (w, _) if w.eq_ignore_ascii_case("first-match") => MatchMode::FirstMatch,
+                (w, span) => {
+                    return Err(DslError::new(
+                        span,
+                        format!("expected priority or first-match, found \"{}\"", w),
+                    ));
+                }
+            };
+            set.ranked = self.eat_keyword("ranked");
+        }
+        while self.peek().is_some() {
+            set.rules.push(self.rule()?);
+        }
+        Ok(set)
+    }
+
+    fn rule(&mut self) -> Result<Rule, DslError> {
+        let action = self
+            .action()
+            .ok_or_else(|| self.unexpected("alert, pickup, ignore or nothing"))?;
+        self.pos += 1;
+        let mut rule = Rule {
+            name: self.code("a rule name")?,
+            action,
+            ..Default::default()
+        };
+
+        loop {
+            if self.eat_keyword("priority") {
+                rule.priority = self.value("a priority from 0 to 255")?;
+            } else if self.eat_keyword("disabled") {
+                rule.enabled = false;
+            } else if self.eat_keyword("notes") {
+                rule.notes = self.code("notes in quotes")?;
+            } else if self.eat_keyword("points") {
+                let negative = self.eat(Tok::Minus);
+                let points: i32 = self.value("a number of points")?;
+                rule.points = if negative { -points } else { points };
+            } else if self.eat_keyword("per") {
+                let field = self
+                    .field()
+                    .ok_or_else(|| self.unexpected("a field like credit or duty"))?;
+                self.pos += 1;
+                rule.per_hour = Some(field);
+            } else {
+                break;
+            }
+        }
+        if self.eat_keyword("when") {
+            let mut alternatives = self.alternatives()?;
+            rule.filters = if alternatives.len() == 1 {
+                alternatives.remove(0)
+            } else {
+                vec![any(alternatives)]
+            };
+        }
+
+        // anything left over has to start the next rule
+        if self.peek().is_some() && self.action().is_none() {
+            return Err(self.unexpected("\"and\", \"or\" or the next rule"));
+        }
+        Ok(rule)
+    }
+
+    fn action(&self) -> Option<BotAction> {
+        let Some((Tok::Word(w), _)) = self.peek() else {
+            return None;
+        };
+        match w.to_lowercase().as_str() {
+            "alert" => Some(BotAction::Alert),
+            "pickup" => Some(BotAction::Pickup),
+            "ignore" => Some(BotAction::Ignore),
+            "nothing" => Some(BotAction::Nothing),
+            _ => None,
+        }
+    }
+
+    // a or b and c, as [[a], [b, c]]
+    fn alternatives(&mut self) -> Result<Vec<Vec<Filter>>, DslError> {
+        let mut alternatives = vec![self.all()?];
+        while self.eat_keyword("or") {
+            alternatives.push(self.all()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn all(&mut self) -> Result<Vec<Filter>, DslError> {
+        let mut filters = vec![self.unary()?];
+        while self.eat_keyword("and") {
+            filters.push(self.unary()?);
+        }
+        Ok(filters)
+    }
+
+    fn unary(&mut self) -> Result<Filter, DslError> {
+        if !self.eat_keyword("not") {
+            return self.filter();
+        }
+        // not (a and b) keeps a and b as the group, like the gui builds it
+        match self.unary()? {
+            Filter::All(filters) => Ok(Filter::Not(filters)),
+            f => Ok(Filter::Not(vec![f])),
+        }
+    }
+
+    fn filter(&mut self) -> Result<Filter, DslError> {
+        if self.eat(Tok::Open) {
+            // () is an empty All, (a) an All of one
+            if self.eat(Tok::Close) {
+                return Ok(Filter::All(vec![]));
+            }
+            let mut alternatives = self.alternatives()?;
+            if !self.eat(Tok::Close) {
+                return Err(self.unexpected("\"and\", \"or\" or \")\""));
+            }
+            return Ok(if alternatives.len() == 1 {
+                Filter::All(alternatives.remove(0))
+            } else {
+                any(alternatives)
+            });
+        }
+
+        if let Some(lhs) = self.field() {
+            self.pos += 1;
+            if self.eat(Tok::Minus) {
+                let rhs = self
+                    .field()
+                    .ok_or_else(|| self.unexpected("a field like report or duty"))?;
+                if lhs.is_clock() != rhs.is_clock() {
+                    let kind = if lhs.is_clock() {
+                        "time of day"
+                    } else {
+                        "duration"
+                    };
+                    return Err(self.unexpected(&format!("a {} field", kind)));
+                }
+                self.pos += 1;
+                let op = self.op()?;
+                return Ok(Filter::TimeDiff(
+                    lhs,
+                    rhs,
+                    op,
+                    self.value("a duration like 12:00")?,
+                ));
+            }
+            let op = self.op()?;
+            let (w, span) = self.word("a time like 10:00")?;
+            let filter = w.parse::<Time>().map(|t| Filter::FieldIs(lhs, op, t));
+            return match filter {
+                Ok(f) if f.mixed_times().is_none() => Ok(f),
+                _ => Err(DslError::new(
+                    span,
+                    format!("expected a time like 10:00, found \"{}\"", w),
+                )),
+            };
+        }
+
+        let (w, span) = self.word("a filter")?;
+        match w.to_lowercase().as_str() {
+            "date" if self.eat_keyword("between") => {
+                let from = self.value("a date")?;
+                self.expect_keyword("and")?;
+                Ok(Filter::DateBetween(from, self.value("a date")?))
+            }
+            "date" => {
+                let op = self.op()?;
+                Ok(Filter::DateIs(
+                    op,
+                    self.value("a date like 2026-01-05 or today+3")?,
+                ))
+            }
+            "weekday" => {
+                self.expect_keyword("in")?;
+                Ok(Filter::OnWeekday(self.list(Self::weekday, "a weekday")?))
+            }
+            "overlaps" => {
+                let date = |p: &Self| match p.peek() {
+                    Some((Tok::Word(w), _)) => w.parse::<DateExpr>().ok(),
+                    _ => None,
+                };
+                Ok(Filter::Overlaps(self.list(date, "a date")?))
+            }
+            "ends" => {
+                self.expect_keyword("before")?;
+                Ok(Filter::EndsBefore(self.value("a date")?))
+            }
+            "end" => {
+                self.expect_keyword("date")?;
+                let op = self.op()?;
+                Ok(Filter::EndDateIs(
+                    op,
+                    self.value("a date like 2026-01-05 or today+3")?,
+                ))
+            }
+            "no" if self.eat_keyword("conflict") => {
+                let rest = if self.eat_keyword("rest") {
+                    self.value("a duration like 10:00")?
+                } else {
+                    Time::default()
+                };
+                Ok(Filter::NoConflict(rest))
+            }
+            "no" if self.eat_keyword("layover") => Ok(Filter::ExcludeLayover(self.codes()?)),
+            "no" => Err(self.unexpected("\"conflict\" or \"layover\"")),
+            "layover" => Ok(Filter::IncludeLayover(self.codes()?)),
+            "international" => Ok(Filter::IsInternational),
+            "days" => {
+                let op = self.op()?;
+                Ok(Filter::NumDays(op, self.value("a number of days")?))
+            }
+            "layovers" => {
+                let op = self.op()?;
+                Ok(Filter::NumLayovers(op, self.value("a number of layovers")?))
+            }
+            "premium" => Ok(Filter::IsPrem),
+            "trip" => Ok(Filter::IncludeId(self.code("a trip id")?)),
+            "never" => Ok(Filter::Any(vec![])),
+            _ => Err(DslError::new(
+                span,
+                format!("expected a filter, found \"{}\"", w),
+            )),
+        }
+    }
+}
+
+// a or (b and c) keeps b and c together in an All
+fn any(alternatives: Vec<Vec<Filter>>) -> Filter {
+    Filter::Any(
+        alternatives
+            .into_iter()
+            .map(|mut all| {
+                if all.len() == 1 {
+                    all.remove(0)
+                } else {
+                    Filter::All(all)
+                }
+            })
+            .collect(),
+    )
+}
+
+// a whole rule set as printed by RuleSet::as_string, the mode line is optional
+pub fn parse_rules(text: &str) -> Result<RuleSet, DslError> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+        end: text.len(),
+    };
+    parser.rule_set()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::Date;
+
+    fn parse_rule(text: &str) -> Result<Rule, DslError> {
+        let mut set = parse_rules(text)?;
+        assert_eq!(set.rules.len(), 1);
+        Ok(set.rules.remove(0))
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rule = parse_rule(
+            r#"alert "late reports" when report >= 10:00 and days <= 2 and not layover LHR"#,
+        )
+        .unwrap();
+
+        assert_eq!(rule.name, "late reports");
+        assert_eq!(rule.action, BotAction::Alert);
+        assert_eq!(
+            rule.filters,
+            [
+                Filter::FieldIs(Field::Report, Op::GtEq, "10:00".parse().unwrap()),
+                Filter::NumDays(Op::LtEq, 2),
+                Filter::Not(vec![Filter::IncludeLayover(vec!["LHR".to_owned()])]),
+            ]
+        );
+
+        let rule = parse_rule(
+            "Pickup \"short\" priority 4 disabled\n\
+             when arrive - report < 09:30 # duty length\n\
+             and (premium or credit >= 6:00 and trip \"W 10\")",
+        )
+        .unwrap();
+        assert_eq!(rule.priority, 4);
+        assert!(!rule.enabled);
+        assert_eq!(
+            rule.filters[1],
+            Filter::Any(vec![
+                Filter::IsPrem,
+                Filter::All(vec![
+                    Filter::FieldIs(Field::Credit, Op::GtEq, Time::from_minutes(6 * 60)),
+                    Filter::IncludeId("W 10".to_owned()),
+                ]),
+            ])
+        );
+    }
+
+    #[test]
+    fn prints_what_it_parses() {
+        let date = DateExpr::On(Date {
+            year: 2026,
+            month: 1,
+            day: 5,
+        });
+        let rule = Rule {
+            name: "every \"filter\"".to_owned(),
+            filters: vec![
+                Filter::TimeDiff(
+                    Field::Arrive,
+                    Field::Report,
+                    Op::Lt,
+                    Time::from_minutes(750),
+                ),
+                Filter::FieldIs(Field::Depart, Op::NEq, Time::from_minutes(60)),
+                Filter::TimeDiff(
+                    Field::Duty,
+                    Field::CreditPerDay,
+                    Op::Gt,
+                    Time::from_minutes(90),
+                ),
+                Filter::FieldIs(Field::Sit, Op::LtEq, Time::from_minutes(150)),
+                Filter::EndDateIs(Op::Lt, date),
+                Filter::NumLayovers(Op::GtEq, 2),
+                Filter::DateIs(Op::Gt, DateExpr::Today(-1)),
+                Filter::DateBetween(date, DateExpr::Today(7)),
+                Filter::OnWeekday(vec![Weekday::Sat, Weekday::Sun]),
+                Filter::Overlaps(vec![]),
+                Filter::EndsBefore(date),
+                Filter::NoConflict(Time::default()),
+                Filter::NoConflict(Time::from_minutes(600)),
+                Filter::Any(vec![
+                    Filter::ExcludeLayover(vec![]),
+                    Filter::IncludeLayover(vec!["LHR".to_owned(), "West Coast".to_owned()]),
+                    Filter::IsInternational,
+                    Filter::All(vec![Filter::IsPrem, Filter::NumDays(Op::Eq, 3)]),
+                    Filter::Any(vec![]),
+                ]),
+                Filter::Not(vec![Filter::All(vec![Filter::IncludeId(
+                    "W1030".to_owned(),
+                )])]),
+                Filter::Not(vec![
+                    Filter::IsPrem,
+                    Filter::IncludeLayover(vec!["DUB".to_owned()]),
+                ]),
+                Filter::Not(vec![]),
+                Filter::All(vec![]),
+            ],
+            action: BotAction::Ignore,
+            priority: 9,
+            enabled: false,
+            notes: "for \"quiet\" months".to_owned(),
+            points: -5,
+            per_hour: Some(Field::CreditPerDay),
+        };
+        let text = rule.as_string();
+        assert_eq!(parse_rule(&text), Ok(rule), "{}", text);
+
+        let set = RuleSet {
+            mode: MatchMode::FirstMatch,
+            ranked: true,
+            rules: vec![
+                parse_rule(r#"nothing "empty""#).unwrap(),
+                parse_rule(r#"alert "a" when layover "LH-R""#).unwrap(),
+            ],
+        };
+        assert_eq!(parse_rules(&set.as_string()), Ok(set));
+    }
+
+    #[test]
+    fn points_at_errors() {
+        let text = "alert \"late\" when report >= 10:00\n  and days <= two";
+        let e = parse_rules(text).unwrap_err();
+        assert_eq!(&text[e.span.clone()], "two");
+        assert_eq!(e.position(text), (2, 15));
+        assert_eq!(
+            e.describe(text),
+            "line 2, column 15: expected a number of days, found \"two\""
+        );
+
+        let e = parse_rules("alert \"late\" when report >= 25:00").unwrap_err();
+        assert_eq!(e.span, 28..33);
+        let text = "alert \"late\" when arrive - block > 1:00";
+        let e = parse_rules(text).unwrap_err();
+        assert_eq!(&text[e.span.clone()], "block");
+        assert_eq!(
+            e.describe(text),
+            "line 1, column 28: expected a time of day field, found \"block\""
+        );
+        let e = parse_rules("alert \"late\" when premium premium").unwrap_err();
+        assert_eq!(e.span, 26..33);
+        let e = parse_rules("alert \"late\" when (premium").unwrap_err();
+        assert_eq!(e.span, 26..26);
+        assert!(parse_rules("alert \"late").is_err());
+        assert!(parse_rules("when premium").is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use enigo::{
+    Button, Coordinate,
+    Direction::{Click, Press, Release},
+    Enigo, InputResult, Key, Keyboard, Mouse, NewConError, Settings,
+};
+use std::{fmt::Display, thread, time::Duration};
+
+// the input the bot sends to the browser, EnigoInput sends it for real and RecordedInput keeps it
+pub trait Input {
+    // holds the modifiers down around a single key, e.g. Control+r
+    fn chord(&mut self, modifiers: &[Key], key: Key) -> InputResult<()>;
+    fn text(&mut self, text: &str) -> InputResult<()>;
+    // left click at a point on the screen
+    fn click(&mut self, x: i32, y: i32) -> InputResult<()>;
+
+    fn key(&mut self, key: Key) -> InputResult<()> {
+        self.chord(&[], key)
+    }
+
+    // a pause for the page to catch up
+    fn wait(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+pub struct EnigoInput(Enigo);
+
+impl EnigoInput {
+    pub fn new() -> Result<Self, NewConError> {
+        Enigo::new(&Settings::default()).map(EnigoInput)
+    }
+}
+
+impl Input for EnigoInput {
+    fn chord(&mut self, modifiers: &[Key], key: Key) -> InputResult<()> {
+        let mut pressed = 0;
+        let result = modifiers
+            .iter()
+            .try_for_each(|m| {
+                self.0.key(*m, Press)?;
+                pressed += 1;
+                Ok(())
+            })
+            .and_then(|()| self.0.key(key, Click));
+
+        // release what was pressed even when a key failed, so no modifier is left held down
+        let mut released = Ok(());
+        for m in modifiers[..pressed].iter().rev() {
+            released = released.and(self.0.key(*m, Release));
+        }
+        result.and(released)
+    }
+
+    fn text(&mut self, text: &str) -> InputResult<()> {
+        self.0.text(text)
+    }
+
+    fn click(&mut self, x: i32, y: i32) -> InputResult<()> {
+        self.0.move_mouse(x, y, Coordinate::Abs)?;
+        self.0.button(Button::Left, Click)
+    }
+}
+
+// one thing sent through Input
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum InputEvent {
+    Chord(Vec<Key>, Key),
+    Text(String),
+    Click(i32, i32),
+    Wait(Duration),
+}
+
+impl Display for InputEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn key_name(key: &Key) -> String {
+            match key {
+                Key::Unicode(c) => c.to_string(),
+                key => format!("{:?}", key),
+            }
+        }
+
+        match self {
+            InputEvent::Chord(modifiers, key) => {
+                for m in modifiers {
+                    write!(f, "{}+", key_name(m))?;
+                }
+                write!(f, "{}", key_name(key))
+            }
+            InputEvent::Text(text) => write!(f, "type \"{}\"", text),
+            InputEvent::Click(x, y) => write!(f, "click at {}, {}", x, y),
+            InputEvent::Wait(d) => write!(f, "wait {}ms", d.as_millis()),
+        }
+    }
+}
+
+// /, type "submit", wait 200ms, Shift+Tab, ...
+pub fn plan(events: &[InputEvent]) -> String {
+    events
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// keeps what would have been sent instead of sending it, for dry runs and tests
+#[derive(Debug, Default)]
+pub struct RecordedInput {
+    pub events: Vec<InputEvent>,
+}
+
+impl Input for RecordedInput {
+    fn chord(&mut self, modifiers: &[Key], key: Key) -> InputResult<()> {
+        self.events.push(InputEvent::Chord(modifiers.to_vec(), key));
+        Ok(())
+    }
+
+    fn text(&mut self, text: &str) -> InputResult<()> {
+        self.events.push(InputEvent::Text(text.to_owned()));
+        Ok(())
+    }
+
+    fn click(&mut self, x: i32, y: i32) -> InputResult<()> {
+        self.events.push(InputEvent::Click(x, y));
+        Ok(())
+    }
+
+    // waits are recorded rather than slept through
+    fn wait(&mut self, duration: Duration) {
+        self.events.push(InputEvent::Wait(duration));
+    }
+}
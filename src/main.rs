@@ -1,27 +1,54 @@
 #![windows_subsystem = "windows"]
 use crate::bot::{
-    BotAction, BotMessage, Date, Field, Filter, FilterType, Op, Rule, Time, bot_thread,
-    image_bot_thread,
+    BotAction, BotMessage, DateExpr, Field, Filter, FilterType, MatchMode, Op, Rule, RuleSet, Time,
+    Weekday, bot_thread, image_bot_thread,
 };
+use crate::credit::CreditError;
+use crate::profiles::Profiles;
+use crate::schedule::{Schedule, ScheduleError};
+use crate::trace::{FilterTrace, TripTrace};
 use iced::Background;
-use iced::widget::{Column, button, checkbox, column, container, row, scrollable, text};
+use iced::widget::{
+    Column, button, checkbox, column, container, mouse_area, row, scrollable, text, text_editor,
+};
 use iced::{
     Border, Center, Color, Element, Length, Padding, Size, Subscription, Task, Theme,
     keyboard::{Key, Modifiers, key, on_key_press},
+    mouse,
 };
 use self_update::cargo_crate_version;
 use std::{
+    collections::{HashMap, HashSet},
     fs::File,
     io::prelude::*,
+    path::PathBuf,
     sync::mpsc::{self, Receiver, Sender},
     thread,
     time::{Duration, Instant},
 };
 
+mod airports;
+mod alarm;
 mod bot;
+mod capture;
+mod cli;
+mod clipboard;
+mod credit;
+mod dsl;
+mod input;
+mod opentime;
+mod profiles;
+mod rules_file;
+mod schedule;
+mod trace;
 mod update;
 
 pub fn main() -> iced::Result {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "eval") {
+        std::process::exit(cli::eval(&args[1..]));
+    }
+
     // handle updates
     let res = update::update();
     println!("{:?}", res);
@@ -51,6 +78,18 @@ fn bordered_box(theme: &Theme) -> container::Style {
     s
 }
 
+// greys out a disabled rule, it stays editable
+fn disabled_box(theme: &Theme) -> container::Style {
+    let mut s = bordered_box(theme);
+    s.text_color = Some(Color {
+        r: 0.5,
+        g: 0.5,
+        b: 0.5,
+        a: 1.0,
+    });
+    s
+}
+
 fn filter_box(theme: &Theme) -> container::Style {
     let mut s = container::bordered_box(theme);
     s.border = s.border.rounded(5);
@@ -87,16 +126,47 @@ enum Message {
     NewRule,
     SaveRules,
     LoadRules,
+    ImportSchedule,
+    SelectProfile(String),
+    ProfileNameChanged(String),
+    ToggleDryRun(bool),
+    DuplicateProfile,
+    RenameProfile,
+    DeleteProfile,
     EnableRule(usize),
     DisableRule(usize),
     DeleteRule(usize),
+    DuplicateRule(usize),
+    MoveRule(usize, usize),
+    RenameRule(usize, String),
+    ChangeRuleNotes(usize, String),
     ChangeRuleAction(usize, BotAction),
+    ChangeRulePriority(usize, u8),
+    ChangeMatchMode(MatchMode),
+    ToggleRanked(bool),
+    ChangeRulePoints(usize, String),
+    ChangeRulePer(usize, PointsPer),
+    DragRule(usize),
+    HoverRule(usize),
+    LeaveRule(usize),
+    EndDrag,
     GotWindowId(iced::window::Id),
-    NewFilter(usize, FilterType),
-    DeleteFilter(usize, usize),
-    UpdateFilter(usize, usize, Filter),
-    UpdateEntry(usize, usize, String),
-    SubmitEntry(usize, usize, Filter),
+    NewFilter(usize, Vec<usize>, FilterType),
+    DeleteFilter(usize, Vec<usize>),
+    UpdateFilter(usize, Vec<usize>, Filter),
+    UpdateEntry(usize, Vec<usize>, String),
+    UpdateEndEntry(usize, Vec<usize>, String),
+    SubmitEntry(usize, Vec<usize>),
+    ToggleGroup(usize, Vec<usize>),
+    ShowTrace(usize),
+    CloseTrace,
+    ExportTrace,
+    EditRulesText,
+    RulesTextAction(text_editor::Action),
+    ApplyRulesText,
+    CloseRulesText,
+    ImportRulesText,
+    ExportRulesText,
 }
 
 #[derive(Debug, Default, Eq, PartialEq)]
@@ -115,6 +185,8 @@ struct App {
     info: InfoPane,
     control_pane: ControlPane,
     rules_pane: RulesPane,
+    profiles: Option<Profiles>,
+    schedule: Schedule,
     bot_handle: Option<iced::task::Handle>,
     rx: Receiver<BotMessage>,
     tx: Sender<BotMessage>,
@@ -126,24 +198,75 @@ impl App {
         let (bm_tx, bm_rx) = mpsc::channel();
         let thread_handle = thread::spawn(move || image_bot_thread(mb_rx, bm_tx));
 
+        let mut log = LogPane::default();
+        let profiles = match Profiles::open() {
+            Ok(profiles) => {
+                if let Err(e) = profiles.import_first_run("rules.json") {
+                    log.push(&format!("rules.json not imported: {}", e));
+                }
+                Some(profiles)
+            }
+            Err(e) => {
+                log.push(&format!("Rule profiles unavailable: {}", e));
+                None
+            }
+        };
+        let schedule = match Schedule::load("schedule.json") {
+            Ok(schedule) => schedule,
+            Err(ScheduleError::Io(_)) => Schedule::default(),
+            Err(e) => {
+                log.push(&e.to_string());
+                Schedule::default()
+            }
+        };
+
+        let mut app = App {
+            window_id: None,
+            state: AppState::default(),
+            log,
+            info: InfoPane::default(),
+            control_pane: ControlPane::default(),
+            rules_pane: RulesPane::default(),
+            profiles,
+            schedule,
+            bot_handle: None,
+            rx: bm_rx,
+            tx: mb_tx,
+        };
+        app.refresh_profiles();
+        if let Some(first) = app.control_pane.profiles.first().cloned() {
+            app.open_profile(first);
+        }
+
         (
-            App {
-                window_id: None,
-                state: AppState::default(),
-                log: LogPane::default(),
-                info: InfoPane::default(),
-                control_pane: ControlPane::default(),
-                rules_pane: RulesPane::default(),
-                bot_handle: None,
-                rx: bm_rx,
-                tx: mb_tx,
-            },
+            app,
             Task::map(iced::window::get_latest(), |m| {
                 Message::GotWindowId(m.unwrap())
             }),
         )
     }
 
+    fn refresh_profiles(&mut self) {
+        self.control_pane.profiles = self.profiles.as_ref().map_or(Vec::new(), |p| p.list());
+    }
+
+    fn open_profile(&mut self, name: String) {
+        let Some(profiles) = &self.profiles else {
+            return;
+        };
+        match profiles.load(&name) {
+            Ok(set) => {
+                self.rules_pane = RulesPane::new(set);
+                self.control_pane.profile = Some(name);
+            }
+            Err(e) => {
+                let error = format!("{}: {}", name, e);
+                self.log.push(&error);
+                self.rules_pane.error = Some(error);
+            }
+        }
+    }
+
     fn update(&mut self, message: Message) -> Task<Message> {
         // this is where you could loop over update calls to chain mwessages
         self.log.update(message.clone());
@@ -162,7 +285,11 @@ impl App {
             Message::Start => {
                 self.state = AppState::Running;
                 self.tx
-                    .send(BotMessage::Start(self.rules_pane.rules.clone()))
+                    .send(BotMessage::Start(
+                        self.rules_pane.rule_set(),
+                        self.schedule.clone(),
+                        self.control_pane.dry_run,
+                    ))
                     .unwrap();
                 Task::none()
             }
@@ -172,16 +299,123 @@ impl App {
                 iced::window::gain_focus(self.window_id.unwrap())
             }
             Message::SaveRules => {
-                if let Ok(js) = serde_json::to_string(&self.rules_pane.rules) {
-                    if let Ok(mut file) = File::create("rules.json") {
-                        let _ = file.write_all(js.as_bytes());
-                    };
+                // with no profile selected this saves a new one under the typed name
+                let name = self
+                    .control_pane
+                    .profile
+                    .clone()
+                    .unwrap_or_else(|| self.control_pane.profile_name.trim().to_owned());
+                let set = self.rules_pane.rule_set();
+                if let Some(profiles) = &self.profiles {
+                    match profiles.save(&name, &set) {
+                        Ok(_) => {
+                            self.refresh_profiles();
+                            self.control_pane.profile = Some(name);
+                        }
+                        Err(e) => self.log.push(&e.to_string()),
+                    }
+                }
+                Task::none()
+            }
+            Message::LoadRules => {
+                if let Some(name) = self.control_pane.profile.clone() {
+                    self.open_profile(name);
+                }
+                Task::none()
+            }
+            Message::SelectProfile(name) => {
+                self.open_profile(name);
+                Task::none()
+            }
+            Message::DuplicateProfile | Message::RenameProfile | Message::DeleteProfile => {
+                let (Some(profiles), Some(from)) = (&self.profiles, &self.control_pane.profile)
+                else {
+                    return Task::none();
+                };
+                let to = self.control_pane.profile_name.trim().to_owned();
+                let result = match message {
+                    // the copy starts from what's on screen, unsaved edits included
+                    Message::DuplicateProfile => profiles.create(&to, &self.rules_pane.rule_set()),
+                    Message::RenameProfile => profiles.rename(from, &to),
+                    _ => profiles.delete(from),
                 };
+                if let Err(e) = result {
+                    self.log.push(&e.to_string());
+                    return Task::none();
+                }
 
+                self.refresh_profiles();
+                if let Message::DeleteProfile = message {
+                    match self.control_pane.profiles.first().cloned() {
+                        Some(name) => self.open_profile(name),
+                        None => {
+                            self.rules_pane = RulesPane::default();
+                            self.control_pane.profile = None;
+                        }
+                    }
+                } else {
+                    self.control_pane.profile = Some(to);
+                    self.control_pane.profile_name.clear();
+                }
                 Task::none()
             }
-            Message::LoadRules => {
-                self.rules_pane = RulesPane::load_file();
+            Message::ImportSchedule => {
+                // export the calendar from the crew portal into the config directory,
+                // without one it's read from the working directory
+                let dir = self
+                    .profiles
+                    .as_ref()
+                    .map_or(PathBuf::from("."), |p| p.config_dir());
+                let imported = ["schedule.ics", "schedule.csv"]
+                    .into_iter()
+                    .map(|f| dir.join(f))
+                    .find(|p| p.exists())
+                    .map(|p| {
+                        let path = p.display().to_string();
+                        let schedule = Schedule::import(&path);
+                        (path, schedule)
+                    });
+                match imported {
+                    Some((path, Ok(schedule))) => {
+                        if let Err(e) = schedule.save("schedule.json") {
+                            self.log.push(&e.to_string());
+                        }
+                        self.log.push(&format!(
+                            "Imported {} schedule items from {}",
+                            schedule.items.len(),
+                            path
+                        ));
+                        self.schedule = schedule;
+                    }
+                    Some((path, Err(e))) => self.log.push(&format!("{}: {}", path, e)),
+                    None => self.log.push(&format!(
+                        "No schedule.ics or schedule.csv in {} to import",
+                        dir.display()
+                    )),
+                }
+                Task::none()
+            }
+            Message::ImportRulesText => {
+                // opened in the text editor, nothing changes until it's applied
+                match std::fs::read_to_string("rules.txt") {
+                    Ok(data) => {
+                        self.rules_pane.text = Some(text_editor::Content::with_text(&data));
+                        self.rules_pane.text_error = None;
+                    }
+                    Err(e) => self.log.push(&format!("Failed to read rules.txt: {}", e)),
+                }
+                Task::none()
+            }
+            Message::ExportRulesText => {
+                let data = match &self.rules_pane.text {
+                    Some(content) => content.text(),
+                    None => self.rules_pane.rule_set().as_string(),
+                };
+                let line = match std::fs::write("rules.txt", data) {
+                    Ok(_) => "Rules saved to rules.txt".to_owned(),
+                    Err(e) => format!("Failed to save rules.txt: {}", e),
+                };
+                self.log.push(&line);
                 Task::none()
             }
             Message::GotWindowId(i) => {
@@ -196,26 +430,53 @@ impl App {
         row![
             container(column![self.log.view(), self.info.view()].spacing(5))
                 .width(Length::FillPortion(3)),
-            container(column![self.control_pane.view(), self.rules_pane.view()].spacing(5))
-                .width(Length::FillPortion(7)),
+            container(
+                column![self.control_pane.view()]
+                    .push_maybe(self.log.trace_view())
+                    .push(self.rules_pane.view())
+                    .spacing(5)
+            )
+            .width(Length::FillPortion(7)),
         ]
         .spacing(5)
         .into()
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        iced::time::every(Duration::from_millis(10)).map(|_| Message::Tick)
+        let tick = iced::time::every(Duration::from_millis(10)).map(|_| Message::Tick);
+        // a drag ends wherever the mouse is let go, over a rule or not
+        if self.rules_pane.dragging.is_some() {
+            Subscription::batch([tick, iced::event::listen_with(end_drag)])
+        } else {
+            tick
+        }
     }
 }
 
+fn end_drag(event: iced::Event, _: iced::event::Status, _: iced::window::Id) -> Option<Message> {
+    matches!(
+        event,
+        iced::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+    )
+    .then_some(Message::EndDrag)
+}
+
 #[derive(Default, Debug)]
 struct ControlPane {
     state: AppState,
+    profiles: Vec<String>,
+    profile: Option<String>,
+    // name for a new, duplicated or renamed profile
+    profile_name: String,
+    // pickups are logged instead of sent, can't be changed while the bot runs
+    dry_run: bool,
 }
 
 impl ControlPane {
     fn update(&mut self, message: Message) {
         match message {
+            Message::ProfileNameChanged(name) => self.profile_name = name,
+            Message::ToggleDryRun(dry_run) => self.dry_run = dry_run,
             Message::Start => {
                 self.state = AppState::Running;
             }
@@ -227,20 +488,43 @@ impl ControlPane {
     }
 
     fn view(&self) -> Element<Message> {
-        container(row![
-            text(format!("Current State: {:?}", self.state)).size(20),
-            if self.state == AppState::Running {
-                button("Start")
-            } else {
-                button("Start").on_press(Message::Start)
-            },
-            if self.state == AppState::Stopped {
-                button("Stop")
-            } else {
-                button("Stop").on_press(Message::Stop)
-            },
-            button("Save").on_press(Message::SaveRules),
-            button("Load").on_press(Message::LoadRules),
+        let selected = self.profile.is_some();
+        let named = !self.profile_name.trim().is_empty();
+
+        container(column![
+            row![
+                text(format!("Current State: {:?}", self.state)).size(20),
+                if self.state == AppState::Running {
+                    button("Start")
+                } else {
+                    button("Start").on_press(Message::Start)
+                },
+                if self.state == AppState::Stopped {
+                    button("Stop")
+                } else {
+                    button("Stop").on_press(Message::Stop)
+                },
+                button("Save").on_press_maybe((selected || named).then_some(Message::SaveRules)),
+                button("Load").on_press_maybe(selected.then_some(Message::LoadRules)),
+                button("Import Schedule").on_press(Message::ImportSchedule),
+                checkbox("Dry run", self.dry_run).on_toggle_maybe(
+                    (self.state == AppState::Stopped).then_some(Message::ToggleDryRun)
+                ),
+            ],
+            row![
+                iced::widget::pick_list(self.profiles.as_slice(), self.profile.as_ref(), |p| {
+                    Message::SelectProfile(p.clone())
+                })
+                .placeholder("Profile"),
+                iced::widget::text_input("new profile name", &self.profile_name)
+                    .on_input(Message::ProfileNameChanged),
+                button("Duplicate")
+                    .on_press_maybe((selected && named).then_some(Message::DuplicateProfile)),
+                button("Rename")
+                    .on_press_maybe((selected && named).then_some(Message::RenameProfile)),
+                button("Delete").on_press_maybe(selected.then_some(Message::DeleteProfile)),
+            ]
+            .spacing(5),
         ])
         .style(bordered_box)
         .height(Length::FillPortion(1))
@@ -252,135 +536,341 @@ impl ControlPane {
 #[derive(Default, Debug)]
 struct RulesPane {
     rules: Vec<Rule>,
-    enabled: Vec<bool>,
-    entries: Vec<Vec<String>>,
+    mode: MatchMode,
+    ranked: bool,
+    // index of the rule being dragged by its handle
+    dragging: Option<usize>,
+    // the rule under the mouse, where a drag ends up
+    hovered: Option<usize>,
+    // why the last profile failed to load, the rules on screen are left as they were
+    error: Option<String>,
+    // text typed into filter inputs that hasn't been applied yet, keyed by filter path
+    entries: Vec<HashMap<Vec<usize>, String>>,
+    // the rule and filter path of the input being typed in
+    editing: Option<(usize, Vec<usize>)>,
+    collapsed: Vec<HashSet<Vec<usize>>>,
+    // the rules as text while they're being edited that way, see dsl.rs
+    text: Option<text_editor::Content>,
+    text_error: Option<String>,
 }
 
 impl RulesPane {
-    fn load_file() -> Self {
-        println!("CALLED");
-        let Ok(mut file) = File::open("rules.json") else {
-            return Self::default();
-        };
-        println!("ASD");
+    fn new(set: RuleSet) -> Self {
+        let RuleSet {
+            mode,
+            ranked,
+            rules,
+        } = set;
+        let entries = rules.iter().map(|_| HashMap::new()).collect();
+        let collapsed = rules.iter().map(|_| HashSet::new()).collect();
+        Self {
+            rules,
+            mode,
+            ranked,
+            dragging: None,
+            hovered: None,
+            error: None,
+            entries,
+            editing: None,
+            collapsed,
+            text: None,
+            text_error: None,
+        }
+    }
 
-        let mut data = String::new();
-        let Ok(_) = file.read_to_string(&mut data) else {
-            return Self::default();
+    fn rule_set(&self) -> RuleSet {
+        RuleSet {
+            mode: self.mode,
+            ranked: self.ranked,
+            rules: self.rules.clone(),
+        }
+    }
+
+    // moves a rule and everything the pane keeps alongside it
+    fn move_rule(&mut self, from: usize, to: usize) {
+        let rule = self.rules.remove(from);
+        self.rules.insert(to, rule);
+        let entries = self.entries.remove(from);
+        self.entries.insert(to, entries);
+        let collapsed = self.collapsed.remove(from);
+        self.collapsed.insert(to, collapsed);
+    }
+
+    // logs and traces name the rule, so new and copied rules don't reuse a name
+    fn unique_name(&self, name: &str) -> String {
+        let taken = |n: &str| self.rules.iter().any(|r| r.name == n);
+        if !taken(name) {
+            return name.to_owned();
+        }
+        (2..)
+            .map(|i| format!("{} {}", name, i))
+            .find(|n| !taken(n))
+            .unwrap_or_default()
+    }
+
+    // applies what was typed into a filter's inputs, invalid text stays in the input to be fixed
+    fn commit_entry(&mut self, ri: usize, path: &[usize]) {
+        let Some(filter) = self.rules.get_mut(ri).and_then(|r| r.filter_mut(path)) else {
+            return;
         };
-        match serde_json::from_str::<Vec<Rule>>(&data) {
-            Ok(rules) => {
-                let enabled: Vec<bool> = rules.iter().map(|_| true).collect();
-                let entries: Vec<Vec<String>> = rules
-                    .iter()
-                    .map(|r| vec![String::new(); r.filters.len()])
-                    .collect();
-                Self {
-                    rules,
-                    enabled,
-                    entries,
-                }
-            }
-            Err(_) => Self::default(),
+        let entries = &mut self.entries[ri];
+        let entry = entries
+            .get(path)
+            .cloned()
+            .unwrap_or_else(|| filter.entry_text());
+        let end_entry = entries
+            .get(&end_path(path))
+            .cloned()
+            .unwrap_or_else(|| filter.end_entry_text());
+        if let Ok(f) = filter.with_entries(&entry, &end_entry) {
+            *filter = f;
+            // the inputs go back to showing the value, tidied up
+            entries.remove(path);
+            entries.remove(&end_path(path));
         }
     }
 
     fn update(&mut self, message: Message) {
+        // text inputs don't say when they lose focus, so an edit is committed once anything
+        // other than typing into the same filter happens
+        match &message {
+            Message::Tick
+            | Message::Bot(_)
+            | Message::HoverRule(_)
+            | Message::LeaveRule(_)
+            | Message::EndDrag => {}
+            // changing the op or field of the filter being typed in keeps what was typed, the
+            // filter in the message was made before the text was applied
+            Message::UpdateEntry(ri, path, _)
+            | Message::UpdateEndEntry(ri, path, _)
+            | Message::UpdateFilter(ri, path, _)
+                if self.editing.as_ref() == Some(&(*ri, path.clone())) => {}
+            _ => {
+                if let Some((ri, path)) = self.editing.take() {
+                    self.commit_entry(ri, &path);
+                }
+            }
+        }
+
         match message {
             Message::NewRule => {
                 self.rules.push(Rule {
-                    name: "Test Rule".to_owned(),
-                    filters: vec![],
-                    action: BotAction::Alert,
+                    name: self.unique_name("New rule"),
+                    ..Default::default()
                 });
-                self.enabled.push(true);
-                self.entries.push(Vec::new());
+                self.entries.push(HashMap::new());
+                self.collapsed.push(HashSet::new());
             }
             Message::DeleteRule(i) => {
                 self.rules.remove(i);
-                self.enabled.remove(i);
                 self.entries.remove(i);
+                self.collapsed.remove(i);
             }
-            Message::ChangeRuleAction(i, a) => {
-                self.rules[i].action = a;
+            Message::DuplicateRule(i) => {
+                let mut rule = self.rules[i].clone();
+                rule.name = self.unique_name(&rule.name);
+                self.rules.insert(i + 1, rule);
+                self.entries.insert(i + 1, self.entries[i].clone());
+                self.collapsed.insert(i + 1, self.collapsed[i].clone());
             }
-            Message::EnableRule(i) => self.enabled[i] = true,
-            Message::DisableRule(i) => self.enabled[i] = false,
-            Message::NewFilter(i, f) => {
-                self.rules[i].filters.push(f.into());
-                self.entries[i].push(String::new());
+            Message::MoveRule(from, to) if to < self.rules.len() => self.move_rule(from, to),
+            Message::RenameRule(i, name) => self.rules[i].name = name,
+            Message::ChangeRuleNotes(i, notes) => self.rules[i].notes = notes,
+            Message::ChangeMatchMode(mode) => self.mode = mode,
+            Message::ToggleRanked(ranked) => self.ranked = ranked,
+            // the rule's own input is kept under the empty filter path
+            Message::ChangeRulePoints(i, s) => match s.trim().parse() {
+                Ok(points) => {
+                    self.rules[i].points = points;
+                    self.entries[i].remove(&vec![]);
+                }
+                Err(_) => {
+                    self.entries[i].insert(vec![], s);
+                }
+            },
+            Message::ChangeRulePer(i, PointsPer(field)) => self.rules[i].per_hour = field,
+            Message::EditRulesText => {
+                self.text = Some(text_editor::Content::with_text(
+                    &self.rule_set().as_string(),
+                ));
+                self.text_error = None;
             }
-            Message::DeleteFilter(ri, i) => {
-                self.rules[ri].filters.remove(i);
-                self.entries[ri].remove(i);
+            Message::RulesTextAction(action) => {
+                if let Some(content) = &mut self.text {
+                    content.perform(action);
+                }
             }
-            Message::UpdateFilter(ri, i, f) => {
-                self.rules[ri].filters[i] = f;
+            Message::ApplyRulesText => {
+                let Some(content) = &self.text else {
+                    return;
+                };
+                let text = content.text();
+                match dsl::parse_rules(&text) {
+                    Ok(set) => *self = RulesPane::new(set),
+                    Err(e) => self.text_error = Some(e.describe(&text)),
+                }
             }
-            Message::UpdateEntry(ri, i, s) => {
-                self.entries[ri][i] = s;
+            Message::CloseRulesText => {
+                self.text = None;
+                self.text_error = None;
             }
-            Message::SubmitEntry(ri, i, f) => match f {
-                Filter::FieldIs(f, o, _) => {
-                    if let Ok(t) = self.entries[ri][i].parse() {
-                        self.rules[ri].filters[i] = Filter::FieldIs(f, o, t);
-                    }
-                }
-                Filter::TimeDiff(f1, f2, o, _) => {
-                    if let Ok(t) = self.entries[ri][i].parse() {
-                        self.rules[ri].filters[i] = Filter::TimeDiff(f1, f2, o, t)
-                    }
+            Message::ChangeRulePriority(i, p) => self.rules[i].priority = p,
+            Message::DragRule(i) => self.dragging = Some(i),
+            Message::HoverRule(i) => self.hovered = Some(i),
+            // the next rule may already be entered
+            Message::LeaveRule(i) if self.hovered == Some(i) => self.hovered = None,
+            Message::EndDrag => match (self.dragging.take(), self.hovered) {
+                (Some(from), Some(to)) if from.max(to) < self.rules.len() => {
+                    self.move_rule(from, to)
                 }
-
-                Filter::DateIs(op, _) => {
-                    if let Ok(d) = self.entries[ri][i].parse() {
-                        self.rules[ri].filters[i] = Filter::DateIs(op, d)
-                    }
+                _ => {}
+            },
+            Message::ChangeRuleAction(i, a) => {
+                self.rules[i].action = a;
+            }
+            Message::EnableRule(i) => self.rules[i].enabled = true,
+            Message::DisableRule(i) => self.rules[i].enabled = false,
+            Message::NewFilter(i, path, f) => {
+                if let Some(group) = self.rules[i].group_mut(&path) {
+                    group.push(f.into());
                 }
-                Filter::NumDays(op, _) => {
-                    if let Ok(num) = self.entries[ri][i].parse() {
-                        self.rules[ri].filters[i] = Filter::NumDays(op, num)
-                    }
+            }
+            Message::DeleteFilter(ri, path) => {
+                let Some((last, parent)) = path.split_last() else {
+                    return;
+                };
+                if let Some(group) = self.rules[ri].group_mut(parent) {
+                    group.remove(*last);
                 }
-                Filter::IncludeLayover(_) => {
-                    self.rules[ri].filters[i] = Filter::IncludeLayover(self.entries[ri][i].clone())
+
+                // entries and collapsed groups are keyed by path, so shift the later siblings
+                self.entries[ri] = self.entries[ri]
+                    .drain()
+                    .filter_map(|(p, e)| Some((shift_path(&p, &path)?, e)))
+                    .collect();
+                self.collapsed[ri] = self.collapsed[ri]
+                    .drain()
+                    .filter_map(|p| shift_path(&p, &path))
+                    .collect();
+            }
+            Message::UpdateFilter(ri, path, f) => {
+                if let Some(filter) = self.rules[ri].filter_mut(&path) {
+                    *filter = f;
                 }
-                Filter::ExcludeLayover(_) => {
-                    self.rules[ri].filters[i] = Filter::ExcludeLayover(self.entries[ri][i].clone())
+                if self.editing.as_ref() == Some(&(ri, path.clone())) {
+                    self.editing = None;
+                    self.commit_entry(ri, &path);
                 }
-                Filter::IncludeId(_) => {
-                    self.rules[ri].filters[i] = Filter::IncludeId(self.entries[ri][i].clone())
+            }
+            Message::UpdateEntry(ri, path, s) => {
+                self.entries[ri].insert(path.clone(), s);
+                self.editing = Some((ri, path));
+            }
+            Message::ToggleGroup(ri, path) => {
+                let collapsed = &mut self.collapsed[ri];
+                if !collapsed.remove(&path) {
+                    collapsed.insert(path);
                 }
-                Filter::IsPrem => {}
-            },
+            }
+            Message::UpdateEndEntry(ri, path, s) => {
+                self.entries[ri].insert(end_path(&path), s);
+                self.editing = Some((ri, path));
+            }
+            Message::SubmitEntry(ri, path) => self.commit_entry(ri, &path),
             _ => {}
         }
     }
 
+    fn text_view<'a>(&'a self, content: &'a text_editor::Content) -> Element<'a, Message> {
+        container(
+            column![
+                row![
+                    button("Apply").on_press(Message::ApplyRulesText),
+                    button("Cancel").on_press(Message::CloseRulesText),
+                    button("Import").on_press(Message::ImportRulesText),
+                    button("Export").on_press(Message::ExportRulesText),
+                ]
+                .spacing(5),
+                text_editor(content)
+                    .font(iced::Font::MONOSPACE)
+                    .height(Length::Fill)
+                    .on_action(Message::RulesTextAction),
+            ]
+            .push_maybe(
+                self.text_error
+                    .as_ref()
+                    .map(|e| text(format!("Not applied, {}", e)).style(text::danger)),
+            )
+            .spacing(5),
+        )
+        .height(Length::FillPortion(9))
+        .width(Length::Fill)
+        .into()
+    }
+
     fn view(&self) -> Element<Message> {
         /*
             pick_list for dropdowns
             checkbox for enabled
         */
+        if let Some(content) = &self.text {
+            return self.text_view(content);
+        }
         container(
             scrollable(
-                column![
-                    column(self.rules.iter().enumerate().map(|(i, r)| r.view(
-                        i,
-                        self.enabled[i],
-                        &self.entries[i]
-                    )))
-                    .spacing(5),
-                    container(
-                        button(
-                            container("New Rule") //.center_x(Length::Fill)
-                        )
-                        .on_press(Message::NewRule) //.width(Length::Fill)
+                column![]
+                    .push_maybe(
+                        self.error.as_ref().map(|e| {
+                            text(format!("Profile not loaded, {}", e)).style(text::danger)
+                        }),
                     )
-                    .center_x(Length::Fill),
-                    //.style(bordered_box),
-                ]
-                .spacing(5),
+                    .push(
+                        column![
+                            container(
+                                row![
+                                    text("Winning rule"),
+                                    iced::widget::pick_list(
+                                        [MatchMode::Priority, MatchMode::FirstMatch],
+                                        Some(self.mode),
+                                        Message::ChangeMatchMode
+                                    ),
+                                    checkbox("Rank by score", self.ranked)
+                                        .on_toggle(Message::ToggleRanked),
+                                    button("Edit as Text").on_press(Message::EditRulesText),
+                                    button("Import").on_press(Message::ImportRulesText),
+                                    button("Export").on_press(Message::ExportRulesText),
+                                ]
+                                .spacing(10)
+                                .align_y(Center)
+                            )
+                            .center_x(Length::Fill),
+                            column(self.rules.iter().enumerate().map(|(i, r)| {
+                                mouse_area(r.view(RuleViewContext {
+                                    index: i,
+                                    last: i + 1 == self.rules.len(),
+                                    mode: self.mode,
+                                    ranked: self.ranked,
+                                    dragging: self.dragging == Some(i),
+                                    entries: &self.entries[i],
+                                    collapsed: &self.collapsed[i],
+                                }))
+                                .on_enter(Message::HoverRule(i))
+                                .on_exit(Message::LeaveRule(i))
+                                .into()
+                            }))
+                            .spacing(5),
+                            container(
+                                button(
+                                    container("New Rule") //.center_x(Length::Fill)
+                                )
+                                .on_press(Message::NewRule) //.width(Length::Fill)
+                            )
+                            .center_x(Length::Fill),
+                            //.style(bordered_box),
+                        ]
+                        .spacing(5),
+                    )
+                    .spacing(5),
             )
             .spacing(5),
         )
@@ -392,183 +882,613 @@ impl RulesPane {
     }
 }
 
+// filters with two text inputs keep the second entry under this key,
+// leaf filters have no children so it can't clash with a real path
+fn end_path(path: &[usize]) -> Vec<usize> {
+    let mut path = path.to_vec();
+    path.push(1);
+    path
+}
+
+// returns the new location of `path` after the filter at `removed` is deleted,
+// or None if `path` was the removed filter or inside it
+fn shift_path(path: &[usize], removed: &[usize]) -> Option<Vec<usize>> {
+    let (last, parent) = removed.split_last()?;
+    let mut path = path.to_vec();
+    if path.len() > parent.len() && path.starts_with(parent) {
+        let i = &mut path[parent.len()];
+        if *i == *last {
+            return None;
+        } else if *i > *last {
+            *i -= 1;
+        }
+    }
+    Some(path)
+}
+
+const FILTER_TYPES: [FilterType; 19] = [
+    FilterType::TimeDiff,
+    FilterType::FieldIs,
+    FilterType::DateIs,
+    FilterType::DateBetween,
+    FilterType::OnWeekday,
+    FilterType::Overlaps,
+    FilterType::EndsBefore,
+    FilterType::EndDateIs,
+    FilterType::NoConflict,
+    FilterType::IncludeLayover,
+    FilterType::ExcludeLayover,
+    FilterType::IsInternational,
+    FilterType::NumDays,
+    FilterType::NumLayovers,
+    FilterType::IsPrem,
+    FilterType::IncludeId,
+    FilterType::All,
+    FilterType::Any,
+    FilterType::Not,
+];
+
+// what a rule's view needs from the rules pane
+struct RuleViewContext<'a> {
+    index: usize,
+    last: bool,
+    mode: MatchMode,
+    ranked: bool,
+    dragging: bool,
+    entries: &'a HashMap<Vec<usize>, String>,
+    collapsed: &'a HashSet<Vec<usize>>,
+}
+
 impl Rule {
-    fn view(&self, index: usize, state: bool, entries: &[String]) -> Element<Message> {
+    fn view<'a>(&'a self, ctx: RuleViewContext<'a>) -> Element<'a, Message> {
         /*
             pick_list for dropdowns
             checkbox for enabled
         */
-        let filters = [
-            FilterType::TimeDiff,
-            FilterType::FieldIs,
-            FilterType::DateIs,
-            FilterType::IncludeLayover,
-            FilterType::ExcludeLayover,
-            FilterType::NumDays,
-            FilterType::IsPrem,
-            FilterType::IncludeId,
-        ];
+        let RuleViewContext {
+            index,
+            last,
+            mode,
+            ranked,
+            dragging,
+            entries,
+            collapsed,
+        } = ctx;
         let actions = [BotAction::Ignore, BotAction::Pickup, BotAction::Alert];
         container(
             column![
                 container(
                     row![
-                        text(&self.name),
-                        checkbox("Enable", state).on_toggle(move |b| if b {
-                            Message::EnableRule(index)
-                        } else {
-                            Message::DisableRule(index)
-                        }),
-                        iced::widget::pick_list(actions, Some(self.action), move |a| {
-                            Message::ChangeRuleAction(index, a)
-                        }),
-                        button("X").on_press(Message::DeleteRule(index))
+                        // drag the handle onto another rule to move this one there
+                        mouse_area(text(if dragging { "Moving" } else { "::" }))
+                            .on_press(Message::DragRule(index))
+                            .interaction(mouse::Interaction::Grab),
+                        iced::widget::text_input("rule name", &self.name)
+                            .on_input(move |s| Message::RenameRule(index, s))
+                            .width(Length::Fixed(150.0)),
                     ]
+                    .push_maybe((mode == MatchMode::Priority).then(|| {
+                        iced::widget::pick_list(
+                            (0..=9).collect::<Vec<u8>>(),
+                            Some(self.priority),
+                            move |p| Message::ChangeRulePriority(index, p),
+                        )
+                    }))
+                    .push(
+                        row![
+                            checkbox("Enable", self.enabled).on_toggle(move |b| if b {
+                                Message::EnableRule(index)
+                            } else {
+                                Message::DisableRule(index)
+                            }),
+                            iced::widget::pick_list(actions, Some(self.action), move |a| {
+                                Message::ChangeRuleAction(index, a)
+                            }),
+                            button("^").on_press_maybe(
+                                (index > 0).then(|| Message::MoveRule(index, index - 1))
+                            ),
+                            button("v").on_press_maybe(
+                                (!last).then_some(Message::MoveRule(index, index + 1))
+                            ),
+                            button("Copy").on_press(Message::DuplicateRule(index)),
+                            button("X").on_press(Message::DeleteRule(index))
+                        ]
+                        .spacing(10)
+                    )
                     .spacing(10),
                 )
                 //.padding(Padding::from(10))
                 .center_x(Length::Fill),
+                iced::widget::text_input("notes", &self.notes)
+                    .on_input(move |s| Message::ChangeRuleNotes(index, s))
+                    .size(12),
+            ]
+            .push_maybe(ranked.then(|| {
+                let points = entries.get(&vec![]);
+                row![
+                    text("Points"),
+                    iced::widget::text_input(
+                        "0",
+                        &points.cloned().unwrap_or_else(|| self.points.to_string())
+                    )
+                    .on_input(move |s| Message::ChangeRulePoints(index, s))
+                    .style(entry_style(points.is_none()))
+                    .width(Length::Fixed(60.0)),
+                    iced::widget::pick_list(
+                        PointsPer::options(),
+                        Some(PointsPer(self.per_hour)),
+                        move |p| Message::ChangeRulePer(index, p)
+                    ),
+                ]
+                .spacing(10)
+                .align_y(Center)
+            }))
+            .push(
                 column(
                     self.filters
                         .iter()
                         .enumerate()
-                        .map(|(i, r)| r.view(index, i, &entries[i]))
+                        .map(|(i, r)| r.view(index, vec![i], entries, collapsed)),
                 )
                 .spacing(5),
+            )
+            .push(
                 container(iced::widget::pick_list(
-                    filters,
+                    FILTER_TYPES,
                     Some(FilterType::NewFilter),
-                    move |f| { Message::NewFilter(index, f) }
+                    move |f| Message::NewFilter(index, Vec::new(), f),
                 ))
                 .center_x(Length::Fill),
-            ]
+            )
             .spacing(5),
         )
-        .style(bordered_box)
+        .style(if self.enabled {
+            bordered_box
+        } else {
+            disabled_box
+        })
         .padding(Padding::from(5))
         .center_x(Length::Fill)
         .into()
     }
 }
 
+// what a rule's points are multiplied by, for the pick list next to them
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct PointsPer(Option<Field>);
+
+impl PointsPer {
+    fn options() -> Vec<PointsPer> {
+        std::iter::once(PointsPer(None))
+            .chain(Field::ALL.into_iter().map(|f| PointsPer(Some(f))))
+            .collect()
+    }
+}
+
+impl std::fmt::Display for PointsPer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            None => write!(f, "per match"),
+            Some(field) => write!(f, "per hour of {}", field),
+        }
+    }
+}
+
+fn on_filter_update<T, F: Fn(T) -> Filter>(
+    ruleindex: usize,
+    path: &[usize],
+    make: F,
+) -> impl Fn(T) -> Message + use<T, F> {
+    let path = path.to_vec();
+    move |value| Message::UpdateFilter(ruleindex, path.clone(), make(value))
+}
+
+fn on_entry_update(ruleindex: usize, path: &[usize]) -> impl Fn(String) -> Message + use<> {
+    let path = path.to_vec();
+    move |new| Message::UpdateEntry(ruleindex, path.clone(), new)
+}
+
+// the second input of filters that have two
+fn on_end_entry_update(ruleindex: usize, path: &[usize]) -> impl Fn(String) -> Message + use<> {
+    let path = path.to_vec();
+    move |new| Message::UpdateEndEntry(ruleindex, path.clone(), new)
+}
+
+// red border on an input holding something that won't parse
+fn entry_style(
+    valid: bool,
+) -> impl Fn(&Theme, iced::widget::text_input::Status) -> iced::widget::text_input::Style {
+    move |theme, status| {
+        let mut s = iced::widget::text_input::default(theme, status);
+        if !valid {
+            s.border.color = theme.palette().danger;
+        }
+        s
+    }
+}
+
 impl Filter {
-    fn view(&self, ruleindex: usize, index: usize, entry: &str) -> Element<Message> {
-        let fields = [
-            Field::Report,
-            Field::Depart,
-            Field::Arrive,
-            Field::Block,
-            Field::Credit,
-        ];
+    // what the filter's text input shows before anything is typed into it
+    fn entry_text(&self) -> String {
+        match self {
+            Filter::TimeDiff(_, _, _, t) | Filter::FieldIs(_, _, t) | Filter::NoConflict(t) => {
+                t.to_string()
+            }
+            Filter::DateIs(_, d)
+            | Filter::DateBetween(d, _)
+            | Filter::EndsBefore(d)
+            | Filter::EndDateIs(_, d) => d.to_string(),
+            Filter::Overlaps(dates) => dates
+                .iter()
+                .map(DateExpr::to_string)
+                .collect::<Vec<String>>()
+                .join(", "),
+            Filter::IncludeLayover(codes) | Filter::ExcludeLayover(codes) => codes.join(", "),
+            Filter::IncludeId(s) => s.clone(),
+            Filter::NumDays(_, num) | Filter::NumLayovers(_, num) => num.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn end_entry_text(&self) -> String {
+        match self {
+            Filter::DateBetween(_, to) => to.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    // the filter with the typed text applied, or a hint about what's wrong with it
+    fn with_entries(&self, entry: &str, end_entry: &str) -> Result<Filter, &'static str> {
+        let (entry, end_entry) = (entry.trim(), end_entry.trim());
+        let date = |s: &str| {
+            s.parse::<DateExpr>()
+                .or(Err("expected a date like 05JAN, 2026-01-05 or today+3"))
+        };
+        let duration = |s: &str| s.parse::<Time>().or(Err("expected a time like 10:00"));
+        let codes = |s: &str| {
+            s.split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(str::to_owned)
+                .collect()
+        };
+
+        let filter = match self.clone() {
+            Filter::TimeDiff(f1, f2, op, _) => Ok(Filter::TimeDiff(f1, f2, op, duration(entry)?)),
+            Filter::FieldIs(f, op, _) => Ok(Filter::FieldIs(f, op, duration(entry)?)),
+            Filter::DateIs(op, _) => Ok(Filter::DateIs(op, date(entry)?)),
+            Filter::DateBetween(_, _) => Ok(Filter::DateBetween(date(entry)?, date(end_entry)?)),
+            Filter::Overlaps(_) => entry
+                .split(',')
+                .filter(|d| !d.trim().is_empty())
+                .map(date)
+                .collect::<Result<_, _>>()
+                .map(Filter::Overlaps),
+            Filter::EndsBefore(_) => Ok(Filter::EndsBefore(date(entry)?)),
+            Filter::EndDateIs(op, _) => Ok(Filter::EndDateIs(op, date(entry)?)),
+            Filter::NoConflict(_) => Ok(Filter::NoConflict(duration(entry)?)),
+            Filter::NumDays(op, _) => entry
+                .parse()
+                .map(|num| Filter::NumDays(op, num))
+                .or(Err("expected a number of days")),
+            Filter::NumLayovers(op, _) => entry
+                .parse()
+                .map(|num| Filter::NumLayovers(op, num))
+                .or(Err("expected a number of layovers")),
+            Filter::IncludeLayover(_) => Ok(Filter::IncludeLayover(codes(entry))),
+            Filter::ExcludeLayover(_) => Ok(Filter::ExcludeLayover(codes(entry))),
+            Filter::IncludeId(_) => Ok(Filter::IncludeId(entry.to_owned())),
+            f @ (Filter::IsPrem
+            | Filter::IsInternational
+            | Filter::OnWeekday(_)
+            | Filter::All(_)
+            | Filter::Any(_)
+            | Filter::Not(_)) => Ok(f),
+        }?;
+        match filter.mixed_times() {
+            Some(e) => Err(e),
+            None => Ok(filter),
+        }
+    }
+
+    fn view<'a>(
+        &'a self,
+        ruleindex: usize,
+        path: Vec<usize>,
+        entries: &'a HashMap<Vec<usize>, String>,
+        collapsed: &'a HashSet<Vec<usize>>,
+    ) -> Element<'a, Message> {
+        let fields = Field::ALL;
         let ops = [Op::Eq, Op::NEq, Op::Lt, Op::LtEq, Op::GtEq, Op::Gt];
+        let weekdays = [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ];
+        // untouched inputs show the filter's current value
+        let entry = entries
+            .get(&path)
+            .cloned()
+            .unwrap_or_else(|| self.entry_text());
+        let end_entry = entries
+            .get(&end_path(&path))
+            .cloned()
+            .unwrap_or_else(|| self.end_entry_text());
+        let error = self.with_entries(&entry, &end_entry).err();
+        let is_collapsed = collapsed.contains(&path);
 
         container(
             column![
                 container(row![
-                    text(self.as_string()),
-                    container(button("Delete").on_press(Message::DeleteFilter(ruleindex, index)))
-                        .align_right(Length::Fill)
+                    match self.children() {
+                        Some(_) => row![
+                            button(if is_collapsed { "+" } else { "-" })
+                                .on_press(Message::ToggleGroup(ruleindex, path.clone())),
+                            text(match self {
+                                Filter::Any(_) => "Any of",
+                                Filter::Not(_) => "Not all of",
+                                _ => "All of",
+                            }),
+                        ]
+                        .spacing(5),
+                        None => row![text(self.as_string())],
+                    },
+                    container(
+                        button("Delete").on_press(Message::DeleteFilter(ruleindex, path.clone()))
+                    )
+                    .align_right(Length::Fill)
                 ]),
                 match *self {
                     Filter::IsPrem => {
                         container(text("Premium only"))
                     }
+                    Filter::IsInternational => {
+                        container(text("Lays over outside the home country"))
+                    }
                     Filter::TimeDiff(f1, f2, op, t) => {
                         container(row![
-                            iced::widget::pick_list(fields, Some(f1), move |new_f1| {
-                                Message::UpdateFilter(
-                                    ruleindex,
-                                    index,
-                                    Filter::TimeDiff(new_f1, f2, op, t),
-                                )
-                            }),
-                            iced::widget::pick_list(fields, Some(f2), move |new_f2| {
-                                Message::UpdateFilter(
-                                    ruleindex,
-                                    index,
-                                    Filter::TimeDiff(f1, new_f2, op, t),
-                                )
-                            }),
-                            iced::widget::pick_list(ops, Some(op), move |new_op| {
-                                Message::UpdateFilter(
-                                    ruleindex,
-                                    index,
-                                    Filter::TimeDiff(f1, f2, new_op, t),
-                                )
-                            }),
-                            iced::widget::text_input("time", &format!("{}", entry))
-                                .on_input(move |new| Message::UpdateEntry(ruleindex, index, new))
-                                .on_submit(Message::SubmitEntry(ruleindex, index, self.clone())),
+                            iced::widget::pick_list(
+                                fields,
+                                Some(f1),
+                                on_filter_update(ruleindex, &path, move |new_f1: Field| {
+                                    // only times of day or only durations are subtracted
+                                    let f2 = if new_f1.is_clock() == f2.is_clock() {
+                                        f2
+                                    } else {
+                                        new_f1
+                                    };
+                                    Filter::TimeDiff(new_f1, f2, op, t)
+                                })
+                            ),
+                            iced::widget::pick_list(
+                                fields
+                                    .into_iter()
+                                    .filter(|f| f.is_clock() == f1.is_clock())
+                                    .collect::<Vec<Field>>(),
+                                Some(f2),
+                                on_filter_update(ruleindex, &path, move |new_f2| {
+                                    Filter::TimeDiff(f1, new_f2, op, t)
+                                })
+                            ),
+                            iced::widget::pick_list(
+                                ops,
+                                Some(op),
+                                on_filter_update(ruleindex, &path, move |new_op| {
+                                    Filter::TimeDiff(f1, f2, new_op, t)
+                                })
+                            ),
+                            iced::widget::text_input("time", &entry)
+                                .on_input(on_entry_update(ruleindex, &path))
+                                .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                                .style(entry_style(error.is_none())),
                         ])
                     }
                     Filter::FieldIs(f, op, t) => {
                         container(row![
-                            iced::widget::pick_list(fields, Some(f), move |new_f| {
-                                Message::UpdateFilter(
-                                    ruleindex,
-                                    index,
-                                    Filter::FieldIs(new_f, op, t),
-                                )
-                            }),
-                            iced::widget::pick_list(ops, Some(op), move |new_op| {
-                                Message::UpdateFilter(
-                                    ruleindex,
-                                    index,
-                                    Filter::FieldIs(f, new_op, t),
-                                )
-                            }),
-                            iced::widget::text_input("time", &format!("{}", entry))
-                                .on_input(move |new| Message::UpdateEntry(ruleindex, index, new))
-                                .on_submit(Message::SubmitEntry(ruleindex, index, self.clone())),
+                            iced::widget::pick_list(
+                                fields,
+                                Some(f),
+                                on_filter_update(ruleindex, &path, move |new_f| {
+                                    Filter::FieldIs(new_f, op, t)
+                                })
+                            ),
+                            iced::widget::pick_list(
+                                ops,
+                                Some(op),
+                                on_filter_update(ruleindex, &path, move |new_op| {
+                                    Filter::FieldIs(f, new_op, t)
+                                })
+                            ),
+                            iced::widget::text_input("time", &entry)
+                                .on_input(on_entry_update(ruleindex, &path))
+                                .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                                .style(entry_style(error.is_none())),
                         ])
                     }
                     Filter::DateIs(op, d) => {
                         container(row![
-                            iced::widget::pick_list(ops, Some(op), move |new_op| {
-                                Message::UpdateFilter(ruleindex, index, Filter::DateIs(new_op, d))
-                            }),
-                            iced::widget::text_input("date", &format!("{}", entry))
-                                .on_input(move |new| Message::UpdateEntry(ruleindex, index, new))
-                                .on_submit(Message::SubmitEntry(ruleindex, index, self.clone())),
+                            iced::widget::pick_list(
+                                ops,
+                                Some(op),
+                                on_filter_update(ruleindex, &path, move |new_op| {
+                                    Filter::DateIs(new_op, d)
+                                })
+                            ),
+                            iced::widget::text_input("05JAN, 2026-01-05 or today+3", &entry)
+                                .on_input(on_entry_update(ruleindex, &path))
+                                .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                                .style(entry_style(error.is_none())),
+                        ])
+                    }
+                    Filter::DateBetween(_, _) => {
+                        container(
+                            row![
+                                iced::widget::text_input("from", &entry)
+                                    .on_input(on_entry_update(ruleindex, &path))
+                                    .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                                    .style(entry_style(entry.trim().parse::<DateExpr>().is_ok())),
+                                text("to"),
+                                iced::widget::text_input("to", &end_entry)
+                                    .on_input(on_end_entry_update(ruleindex, &path))
+                                    .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                                    .style(entry_style(
+                                        end_entry.trim().parse::<DateExpr>().is_ok()
+                                    )),
+                            ]
+                            .spacing(5),
+                        )
+                    }
+                    Filter::OnWeekday(ref days) => {
+                        container(
+                            row(weekdays.map(|day| {
+                                let days = days.clone();
+                                checkbox(day.to_string(), days.contains(&day))
+                                    .on_toggle(on_filter_update(ruleindex, &path, move |on| {
+                                        let mut days: Vec<Weekday> =
+                                            days.iter().copied().filter(|d| *d != day).collect();
+                                        if on {
+                                            days.push(day);
+                                            days.sort();
+                                        }
+                                        Filter::OnWeekday(days)
+                                    }))
+                                    .into()
+                            }))
+                            .spacing(10),
+                        )
+                    }
+                    Filter::Overlaps(_) => {
+                        container(row![
+                            iced::widget::text_input("dates, comma separated", &entry)
+                                .on_input(on_entry_update(ruleindex, &path))
+                                .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                                .style(entry_style(error.is_none())),
+                        ])
+                    }
+                    Filter::EndsBefore(_) => {
+                        container(row![
+                            iced::widget::text_input("05JAN, 2026-01-05 or today+3", &entry)
+                                .on_input(on_entry_update(ruleindex, &path))
+                                .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                                .style(entry_style(error.is_none())),
+                        ])
+                    }
+                    Filter::EndDateIs(op, d) => {
+                        container(row![
+                            iced::widget::pick_list(
+                                ops,
+                                Some(op),
+                                on_filter_update(ruleindex, &path, move |new_op| {
+                                    Filter::EndDateIs(new_op, d)
+                                })
+                            ),
+                            iced::widget::text_input("05JAN, 2026-01-05 or today+3", &entry)
+                                .on_input(on_entry_update(ruleindex, &path))
+                                .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                                .style(entry_style(error.is_none())),
+                        ])
+                    }
+                    Filter::NoConflict(_) => {
+                        container(row![
+                            text("Minimum rest"),
+                            iced::widget::text_input("time", &entry)
+                                .on_input(on_entry_update(ruleindex, &path))
+                                .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                                .style(entry_style(error.is_none())),
                         ])
                     }
                     Filter::IncludeLayover(_) => {
                         container(row![
-                            iced::widget::text_input("Airport Code", &format!("{}", entry))
-                                .on_input(move |new| Message::UpdateEntry(ruleindex, index, new))
-                                .on_submit(Message::SubmitEntry(ruleindex, index, self.clone())),
+                            iced::widget::text_input(
+                                "airport codes or groups, comma separated",
+                                &entry
+                            )
+                            .on_input(on_entry_update(ruleindex, &path))
+                            .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                            .style(entry_style(error.is_none())),
                         ])
                     }
                     Filter::ExcludeLayover(_) => {
                         container(row![
-                            iced::widget::text_input("Airport Code", &format!("{}", entry))
-                                .on_input(move |new| Message::UpdateEntry(ruleindex, index, new))
-                                .on_submit(Message::SubmitEntry(ruleindex, index, self.clone())),
+                            iced::widget::text_input(
+                                "airport codes or groups, comma separated",
+                                &entry
+                            )
+                            .on_input(on_entry_update(ruleindex, &path))
+                            .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                            .style(entry_style(error.is_none())),
                         ])
                     }
                     Filter::NumDays(op, num) => {
                         container(row![
-                            iced::widget::pick_list(ops, Some(op), move |new_op| {
-                                Message::UpdateFilter(
-                                    ruleindex,
-                                    index,
-                                    Filter::NumDays(new_op, num),
-                                )
-                            }),
-                            iced::widget::text_input("Number of Days", &format!("{}", entry))
-                                .on_input(move |new| Message::UpdateEntry(ruleindex, index, new))
-                                .on_submit(Message::SubmitEntry(ruleindex, index, self.clone())),
+                            iced::widget::pick_list(
+                                ops,
+                                Some(op),
+                                on_filter_update(ruleindex, &path, move |new_op| {
+                                    Filter::NumDays(new_op, num)
+                                })
+                            ),
+                            iced::widget::text_input("Number of Days", &entry)
+                                .on_input(on_entry_update(ruleindex, &path))
+                                .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                                .style(entry_style(error.is_none())),
+                        ])
+                    }
+                    Filter::NumLayovers(op, num) => {
+                        container(row![
+                            iced::widget::pick_list(
+                                ops,
+                                Some(op),
+                                on_filter_update(ruleindex, &path, move |new_op| {
+                                    Filter::NumLayovers(new_op, num)
+                                })
+                            ),
+                            iced::widget::text_input("Number of Layovers", &entry)
+                                .on_input(on_entry_update(ruleindex, &path))
+                                .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                                .style(entry_style(error.is_none())),
                         ])
                     }
                     Filter::IncludeId(_) => {
                         container(row![
-                            iced::widget::text_input("Trip ID", &format!("{}", entry))
-                                .on_input(move |new| Message::UpdateEntry(ruleindex, index, new))
-                                .on_submit(Message::SubmitEntry(ruleindex, index, self.clone())),
+                            iced::widget::text_input("Trip ID", &entry)
+                                .on_input(on_entry_update(ruleindex, &path))
+                                .on_submit(Message::SubmitEntry(ruleindex, path.clone()))
+                                .style(entry_style(error.is_none())),
                         ])
                     }
+                    Filter::All(ref filters)
+                    | Filter::Any(ref filters)
+                    | Filter::Not(ref filters) => {
+                        if is_collapsed {
+                            container(text(self.as_string()))
+                        } else {
+                            container(
+                                column![
+                                    column(filters.iter().enumerate().map(|(i, f)| {
+                                        let mut child = path.clone();
+                                        child.push(i);
+                                        f.view(ruleindex, child, entries, collapsed)
+                                    }))
+                                    .spacing(5),
+                                    container(iced::widget::pick_list(
+                                        FILTER_TYPES,
+                                        Some(FilterType::NewFilter),
+                                        move |f| Message::NewFilter(ruleindex, path.clone(), f)
+                                    ))
+                                    .center_x(Length::Fill),
+                                ]
+                                .spacing(5),
+                            )
+                        }
+                    }
                 }
             ]
+            .push_maybe(error.map(|e| text(e).size(12).style(text::danger)))
             .spacing(5),
         )
         .padding(Padding::from(5))
@@ -578,41 +1498,189 @@ impl Filter {
     }
 }
 
+#[derive(Debug)]
+enum LogLine {
+    Text(String),
+    Trip(TripTrace),
+}
+
 #[derive(Default, Debug)]
 struct LogPane {
-    log: String,
+    log: Vec<LogLine>,
+    selected: Option<usize>,
 }
 
 impl LogPane {
     fn update(&mut self, message: Message) {
         match message {
             Message::Start => {
-                self.log.push_str("Starting Bot\n");
+                self.push("Starting Bot");
             }
             Message::Stop => {
-                self.log.push_str("Bot Stopped\n");
+                self.push("Bot Stopped");
             }
             Message::TripFound => {
-                self.log.push_str("Trip Found\n");
+                self.push("Trip Found");
+            }
+            Message::Bot(BotMessage::Evaluated(t)) => {
+                self.log.push(LogLine::Trip(t));
+            }
+            Message::Bot(BotMessage::DryRunPickup(trip, events)) => {
+                self.push(&format!(
+                    "[Dry run] would pick up {}: {}",
+                    trip,
+                    input::plan(&events)
+                ));
+            }
+            Message::Bot(BotMessage::CopyFailed(e)) => {
+                self.push(&format!("[Bot] Copy failed, {}", e));
+            }
+            Message::Bot(BotMessage::BadRow(d)) => {
+                self.push(&format!("[Bot] {}", d));
+            }
+            Message::Bot(BotMessage::PickupFailed(trip, e)) => {
+                self.push(&format!("[Bot] Pickup of {} failed, {}", trip, e));
+            }
+            Message::Bot(BotMessage::CreditFailed(e @ CreditError::Save(_))) => {
+                self.push(&format!("[Bot] {}", e));
+            }
+            Message::Bot(BotMessage::CreditFailed(e)) => {
+                self.push(&format!("[Bot] {}, pickups will only alert", e));
+            }
+            Message::Bot(BotMessage::CaptureFailed(e)) => {
+                self.push(&format!("[Bot] {}, stopping", e));
+            }
+            Message::Bot(BotMessage::InputFailed(e)) => {
+                self.push(&format!("[Bot] Input failed, {}, stopping", e));
+            }
+            Message::Bot(BotMessage::ReplayFinished) => {
+                self.push("[Bot] Replay finished");
             }
             Message::Bot(m) => {
-                self.log.push_str(&format!("[Bot] {:?}\n", m));
+                self.push(&format!("[Bot] {:?}", m));
+            }
+            Message::ShowTrace(i) => self.selected = Some(i),
+            Message::CloseTrace => self.selected = None,
+            Message::ExportTrace => {
+                let Some(LogLine::Trip(t)) = self.selected.and_then(|i| self.log.get(i)) else {
+                    return;
+                };
+                let path = format!("trace-{}.json", t.trip);
+                let line = match File::create(&path)
+                    .and_then(|mut f| f.write_all(t.to_json().as_bytes()))
+                {
+                    Ok(_) => format!("Trace saved to {}", path),
+                    Err(e) => format!("Failed to save {}: {}", path, e),
+                };
+                self.push(&line);
             }
             _ => {}
         }
     }
 
+    fn push(&mut self, line: &str) {
+        self.log.push(LogLine::Text(line.to_owned()));
+    }
+
     fn view(&self) -> Element<Message> {
         container(
-            scrollable(text(&self.log))
-                .anchor_bottom()
-                .width(Length::Fill),
+            scrollable(column(self.log.iter().enumerate().map(|(i, line)| {
+                match line {
+                    LogLine::Text(s) => text(s).into(),
+                    LogLine::Trip(t) => button(text(format!(
+                        "{}{}",
+                        match &t.decided_by {
+                            Some(rule) => format!("[Bot] {} {} ({})", t.trip, t.action, rule),
+                            None => format!("[Bot] {} {}", t.trip, t.action),
+                        },
+                        t.score.map(|s| format!(" score {}", s)).unwrap_or_default()
+                    )))
+                    .style(button::text)
+                    .padding(0)
+                    .on_press(Message::ShowTrace(i))
+                    .into(),
+                }
+            })))
+            .anchor_bottom()
+            .width(Length::Fill),
         )
         .height(Length::FillPortion(7))
         .width(Length::Fill)
         .style(bordered_box)
         .into()
     }
+
+    fn trace_view(&self) -> Option<Element<Message>> {
+        let LogLine::Trip(t) = self.log.get(self.selected?)? else {
+            return None;
+        };
+
+        Some(
+            container(column![
+                row![
+                    text(format!(
+                        "Trip {}: {} ({}){}",
+                        t.trip,
+                        t.action,
+                        t.decided_by.as_deref().unwrap_or("no rule matched"),
+                        t.score
+                            .map(|s| format!(", score {}", s))
+                            .unwrap_or_default()
+                    ))
+                    .size(18),
+                    container(
+                        row![
+                            button("Export JSON").on_press(Message::ExportTrace),
+                            button("Close").on_press(Message::CloseTrace),
+                        ]
+                        .spacing(5)
+                    )
+                    .align_right(Length::Fill),
+                ],
+                scrollable(column(t.rules.iter().map(|r| {
+                    column![
+                        text(format!(
+                            "{} {} -> {}{}{}",
+                            pass_label(r.passed),
+                            r.rule,
+                            r.action,
+                            if r.enabled { "" } else { " (disabled)" },
+                            if r.points != 0 {
+                                format!(" {:+} points", r.points)
+                            } else {
+                                String::new()
+                            }
+                        )),
+                        column(r.filters.iter().map(filter_trace_view)),
+                    ]
+                    .into()
+                })))
+                .width(Length::Fill),
+            ])
+            .style(bordered_box)
+            .padding(Padding::from(5))
+            .height(Length::FillPortion(4))
+            .width(Length::Fill)
+            .into(),
+        )
+    }
+}
+
+fn pass_label(passed: bool) -> &'static str {
+    if passed { "[PASS]" } else { "[FAIL]" }
+}
+
+fn filter_trace_view(f: &FilterTrace) -> Element<Message> {
+    container(column![
+        text(if f.actual.is_empty() {
+            format!("{} {}", pass_label(f.passed), f.filter)
+        } else {
+            format!("{} {}    ({})", pass_label(f.passed), f.filter, f.actual)
+        }),
+        column(f.children.iter().map(filter_trace_view)),
+    ])
+    .padding(Padding::ZERO.left(15))
+    .into()
 }
 
 #[derive(Debug)]
@@ -644,3 +1712,41 @@ impl InfoPane {
         .into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changing_op_keeps_typed_value() {
+        let mut pane = RulesPane::new(RuleSet {
+            rules: vec![Rule {
+                filters: vec![Filter::FieldIs(
+                    Field::Credit,
+                    Op::GtEq,
+                    Time::from_minutes(5 * 60),
+                )],
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        pane.update(Message::UpdateEntry(0, vec![0], "6:30".to_owned()));
+        // the pick list's filter still has the value from before the edit
+        pane.update(Message::UpdateFilter(
+            0,
+            vec![0],
+            Filter::FieldIs(Field::Credit, Op::Lt, Time::from_minutes(5 * 60)),
+        ));
+        assert_eq!(
+            pane.rules[0].filters,
+            [Filter::FieldIs(
+                Field::Credit,
+                Op::Lt,
+                Time::from_minutes(6 * 60 + 30)
+            )]
+        );
+        assert!(pane.entries[0].is_empty());
+        assert_eq!(pane.editing, None);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::bot::{Date, Field, Time, Trip};
+use std::fmt::Display;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TripField {
+    Date,
+    Days,
+    Time(Field),
+    Layover,
+}
+
+impl Display for TripField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TripField::Date => write!(f, "Date"),
+            TripField::Days => write!(f, "days"),
+            TripField::Time(field) => write!(f, "{} Time", field),
+            TripField::Layover => write!(f, "layover"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseDiagnostic {
+    // 1 based, like an editor
+    pub line: usize,
+    pub column: usize,
+    // index of the table row the line belongs to
+    pub row: usize,
+    pub field: TripField,
+    // empty when the row stops before the field
+    pub text: String,
+}
+
+impl Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.text.is_empty() {
+            return write!(
+                f,
+                "line {}, column {}: missing {}",
+                self.line, self.column, self.field
+            );
+        }
+        write!(
+            f,
+            "line {}, column {}: invalid {} \"{}\"",
+            self.line, self.column, self.field, self.text
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ParsedPage {
+    pub trips: Vec<Trip>,
+    pub diagnostics: Vec<ParseDiagnostic>,
+}
+
+impl ParsedPage {
+    // the trip in table row `index`, or None if that row failed to parse
+    pub fn row(&self, index: usize) -> Option<&Trip> {
+        let mut skipped = 0;
+        for d in &self.diagnostics {
+            if d.row == index {
+                return None;
+            } else if d.row < index {
+                skipped += 1;
+            }
+        }
+        self.trips.get(index - skipped)
+    }
+}
+
+// a trip row is at least 8 columns wide and starts with a pairing id containing a digit,
+// everything else on the page (headers, menus, footers) is skipped
+fn is_trip_row(tokens: &[(usize, &str)]) -> bool {
+    tokens.len() >= 8
+        && tokens[0].1.chars().all(|c| c.is_ascii_alphanumeric())
+        && tokens[0].1.chars().any(|c| c.is_ascii_digit())
+}
+
+// a row that's too short is still a trip if it starts with a pairing id like W1030,
+// a number alone could be the "4 pairings shown" footer
+fn is_short_trip_row(tokens: &[(usize, &str)]) -> bool {
+    tokens.first().is_some_and(|(_, id)| {
+        id.chars().all(|c| c.is_ascii_alphanumeric())
+            && id.chars().any(|c| c.is_ascii_digit())
+            && id.chars().any(|c| c.is_ascii_alphabetic())
+    })
+}
+
+// the columns after the pairing id, in order
+const COLUMNS: [TripField; 7] = [
+    TripField::Date,
+    TripField::Days,
+    TripField::Time(Field::Report),
+    TripField::Time(Field::Depart),
+    TripField::Time(Field::Arrive),
+    TripField::Time(Field::Block),
+    TripField::Time(Field::Credit),
+];
+
+fn tokenize(line: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in line.char_indices() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(i),
+            (true, Some(s)) => {
+                tokens.push((s, &line[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        tokens.push((s, &line[s..]));
+    }
+    tokens
+}
+
+fn parse_row(tokens: &[(usize, &str)], today: Date) -> Result<Trip, (usize, TripField)> {
+    let time = |i: usize, f: Field| -> Result<Time, (usize, TripField)> {
+        match tokens[i].1.parse::<Time>() {
+            Ok(t) if t.is_clock() || !f.is_clock() => Ok(t),
+            _ => Err((i, TripField::Time(f))),
+        }
+    };
+
+    let mut rest = &tokens[8..];
+    let premium = rest.last().is_some_and(|(_, s)| *s == "X");
+    if premium {
+        rest = &rest[..rest.len() - 1];
+    }
+    if let Some(i) = rest.iter().position(|(_, s)| s.chars().count() != 3) {
+        return Err((8 + i, TripField::Layover));
+    }
+
+    Ok(Trip {
+        id: tokens[0].1.to_owned(),
+        date: Date::parse_from(tokens[1].1, today).or(Err((1, TripField::Date)))?,
+        days: tokens[2].1.parse().or(Err((2, TripField::Days)))?,
+        report: time(3, Field::Report)?,
+        depart: time(4, Field::Depart)?,
+        arrive: time(5, Field::Arrive)?,
+        block: time(6, Field::Block)?,
+        credit: time(7, Field::Credit)?,
+        layovers: rest.iter().map(|(_, s)| (*s).to_owned()).collect(),
+        premium,
+    })
+}
+
+// the board leaves the year off, dates are taken to be the next one on or after `today`
+pub fn parse(text: &str, today: Date) -> ParsedPage {
+    let mut page = ParsedPage::default();
+    let mut row = 0;
+
+    for (line_idx, line) in text.lines().enumerate() {
+        let tokens = tokenize(line);
+        if !is_trip_row(&tokens) {
+            // the first column that isn't there, just past the end of the line
+            if is_short_trip_row(&tokens) {
+                page.diagnostics.push(ParseDiagnostic {
+                    line: line_idx + 1,
+                    column: line.trim_end().chars().count() + 1,
+                    row,
+                    field: COLUMNS[tokens.len() - 1],
+                    text: String::new(),
+                });
+                row += 1;
+            }
+            continue;
+        }
+
+        match parse_row(&tokens, today) {
+            Ok(trip) => page.trips.push(trip),
+            Err((i, field)) => page.diagnostics.push(ParseDiagnostic {
+                line: line_idx + 1,
+                column: line[..tokens[i].0].chars().count() + 1,
+                row,
+                field,
+                text: tokens[i].1.to_owned(),
+            }),
+        }
+        row += 1;
+    }
+
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const TODAY: Date = Date {
+        year: 2025,
+        month: 1,
+        day: 1,
+    };
+
+    // renders a parsed page the way the .golden files are written
+    fn render(page: &ParsedPage) -> String {
+        let mut out = String::new();
+        for t in &page.trips {
+            out.push_str(&format!(
+                "{} {} {}d report {} depart {} arrive {} block {} credit {} [{}]{}\n",
+                t.id,
+                t.date,
+                t.days,
+                t.report,
+                t.depart,
+                t.arrive,
+                t.block,
+                t.credit,
+                t.layovers.join(" "),
+                if t.premium { " premium" } else { "" }
+            ));
+        }
+        for d in &page.diagnostics {
+            out.push_str(&format!("row {} {}\n", d.row, d));
+        }
+        out
+    }
+
+    // set UPDATE_GOLDEN=1 to rewrite the .golden files after an intended change
+    #[test]
+    fn golden_corpus() {
+        let dir = format!("{}/tests/fixtures/opentime", env!("CARGO_MANIFEST_DIR"));
+        let mut cases = 0;
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|e| e != "txt") {
+                continue;
+            }
+
+            let actual = render(&parse(&fs::read_to_string(&path).unwrap(), TODAY));
+            let golden = path.with_extension("golden");
+            if std::env::var_os("UPDATE_GOLDEN").is_some() {
+                fs::write(&golden, &actual).unwrap();
+            }
+            let expected = fs::read_to_string(&golden)
+                .unwrap_or_else(|e| panic!("failed to read {}: {e}", golden.display()));
+            assert_eq!(actual, expected, "{} differs from golden", path.display());
+            cases += 1;
+        }
+        assert!(cases > 0, "no cases found in {dir}");
+    }
+
+    #[test]
+    fn rows_skip_bad_lines() {
+        let page = parse(
+            "A100 05JAN 1 0600 0700 1800 1000 1100 X\n\
+             A200 05JAN x 0600 0700 1800 1000 1100\n\
+             A300 06JAN 2 0600 0700 1800 1000 1100 DUB X\n",
+            TODAY,
+        );
+
+        assert_eq!(page.row(0).map(|t| t.id.as_str()), Some("A100"));
+        assert_eq!(page.row(1), None);
+        assert_eq!(page.row(2).map(|t| t.id.as_str()), Some("A300"));
+        assert_eq!(page.row(3), None);
+    }
+
+    #[test]
+    fn dates_roll_over_the_year() {
+        let today = Date {
+            year: 2025,
+            month: 12,
+            day: 30,
+        };
+        let page = parse(
+            "A100 31DEC 1 0600 0700 1800 1000 1100\n\
+             A200 02JAN 1 0600 0700 1800 1000 1100\n\
+             A300 29FEB 1 0600 0700 1800 1000 1100\n",
+            today,
+        );
+        let dates: Vec<String> = page.trips.iter().map(|t| t.date.to_string()).collect();
+
+        assert_eq!(dates, ["DEC 31, 2025", "JAN 02, 2026", "FEB 29, 2028"]);
+        assert!(page.trips[0].date < page.trips[1].date);
+    }
+}
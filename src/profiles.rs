@@ -0,0 +1,194 @@
+use crate::{
+    bot::RuleSet,
+    rules_file::{self, LoadRulesError, load_rules},
+};
+use std::{fmt::Display, fs, io, path::PathBuf};
+
+// named rule sets, one <name>.json each in the user's config directory
+#[derive(Debug, Clone)]
+pub struct Profiles {
+    dir: PathBuf,
+}
+
+impl Profiles {
+    pub fn open() -> Result<Self, ProfileError> {
+        let dir = dirs::config_dir()
+            .ok_or(ProfileError::NoConfigDir)?
+            .join("hungrychicken")
+            .join("profiles");
+        Profiles::in_dir(dir)
+    }
+
+    // the first time, an old rules file becomes the "default" profile,
+    // one that can't be loaded is left where it is so it can be fixed and imported next time
+    pub fn import_first_run(&self, path: &str) -> Result<(), ProfileError> {
+        if !self.list().is_empty() {
+            return Ok(());
+        }
+        match load_rules(path) {
+            Ok(set) => self.save("default", &set),
+            Err(LoadRulesError::Io(e)) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(ProfileError::Load(e)),
+        }
+    }
+
+    pub fn in_dir(dir: PathBuf) -> Result<Self, ProfileError> {
+        fs::create_dir_all(&dir).map_err(ProfileError::Io)?;
+        Ok(Profiles { dir })
+    }
+
+    // the folder above the profiles, where calendar exports are read from
+    pub fn config_dir(&self) -> PathBuf {
+        self.dir.parent().unwrap_or(&self.dir).to_path_buf()
+    }
+
+    pub fn list(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| {
+                let path = e.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_str()?.to_owned())
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, ProfileError> {
+        // the name is the file name, so keep it from reaching outside the directory
+        let name = name.trim();
+        if name.is_empty()
+            || name.starts_with('.')
+            || name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|'])
+        {
+            return Err(ProfileError::InvalidName(name.to_owned()));
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    fn new_path(&self, name: &str) -> Result<PathBuf, ProfileError> {
+        let path = self.path(name)?;
+        if path.exists() {
+            return Err(ProfileError::Exists(name.trim().to_owned()));
+        }
+        Ok(path)
+    }
+
+    pub fn load(&self, name: &str) -> Result<RuleSet, ProfileError> {
+        let path = self.path(name)?;
+        load_rules(&path.to_string_lossy()).map_err(ProfileError::Load)
+    }
+
+    pub fn save(&self, name: &str, set: &RuleSet) -> Result<(), ProfileError> {
+        rules_file::save_rules(&self.path(name)?.to_string_lossy(), set).map_err(ProfileError::Io)
+    }
+
+    // like save, but won't overwrite another profile
+    pub fn create(&self, name: &str, set: &RuleSet) -> Result<(), ProfileError> {
+        self.new_path(name)?;
+        self.save(name, set)
+    }
+
+    pub fn rename(&self, from: &str, to: &str) -> Result<(), ProfileError> {
+        fs::rename(self.path(from)?, self.new_path(to)?).map_err(ProfileError::Io)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), ProfileError> {
+        fs::remove_file(self.path(name)?).map_err(ProfileError::Io)
+    }
+}
+
+#[derive(Debug)]
+pub enum ProfileError {
+    NoConfigDir,
+    InvalidName(String),
+    Exists(String),
+    Io(std::io::Error),
+    Load(LoadRulesError),
+}
+
+impl Display for ProfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileError::NoConfigDir => write!(f, "no config directory for this user"),
+            ProfileError::InvalidName(n) => write!(f, "\"{}\" can't be used as a profile name", n),
+            ProfileError::Exists(n) => write!(f, "a profile named \"{}\" already exists", n),
+            ProfileError::Io(e) => write!(f, "{}", e),
+            ProfileError::Load(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{MatchMode, Rule};
+
+    #[test]
+    fn manages_profiles() {
+        let dir = std::env::temp_dir().join("hungrychicken-profiles-test");
+        let _ = fs::remove_dir_all(&dir);
+        let profiles = Profiles::in_dir(dir.clone()).unwrap();
+        let set = RuleSet {
+            mode: MatchMode::FirstMatch,
+            ranked: false,
+            rules: vec![Rule {
+                name: "premium".to_owned(),
+                ..Default::default()
+            }],
+        };
+
+        profiles.save("weekday turns", &set).unwrap();
+        profiles.create("international only", &set).unwrap();
+        assert_eq!(profiles.list(), ["international only", "weekday turns"]);
+        assert_eq!(profiles.load("international only").unwrap(), set);
+
+        assert!(matches!(
+            profiles.rename("weekday turns", "international only"),
+            Err(ProfileError::Exists(_))
+        ));
+        assert!(matches!(
+            profiles.create("weekday turns", &RuleSet::default()),
+            Err(ProfileError::Exists(_))
+        ));
+        assert!(matches!(
+            profiles.save("../rules", &set),
+            Err(ProfileError::InvalidName(_))
+        ));
+        profiles.rename("weekday turns", "weekends").unwrap();
+        profiles.delete("international only").unwrap();
+        assert_eq!(profiles.list(), ["weekends"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn imports_old_rules_file() {
+        let dir = std::env::temp_dir().join("hungrychicken-import-test");
+        let _ = fs::remove_dir_all(&dir);
+        let profiles = Profiles::in_dir(dir.join("profiles")).unwrap();
+        let old = dir.join("rules.json");
+        let old = old.to_string_lossy();
+
+        // no old file, nothing to import
+        profiles.import_first_run(&old).unwrap();
+        assert!(profiles.list().is_empty());
+
+        fs::write(&*old, "[{\"name\": ").unwrap();
+        assert!(matches!(
+            profiles.import_first_run(&old),
+            Err(ProfileError::Load(LoadRulesError::Parse(_)))
+        ));
+        assert!(profiles.list().is_empty());
+
+        fs::write(&*old, rules_file::to_json(&RuleSet::default())).unwrap();
+        profiles.import_first_run(&old).unwrap();
+        assert_eq!(profiles.list(), ["default"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
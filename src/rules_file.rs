@@ -0,0 +1,230 @@
+use crate::bot::{BotAction, RuleSet};
+use serde_json::{Value, json};
+use std::{fmt::Display, fs};
+
+// bump this and add a migration whenever a saved rule set would no longer load as is,
+// e.g. after renaming a Filter variant or adding a required field
+pub const VERSION: u64 = 1;
+
+// MIGRATIONS[n] upgrades a version n file to version n + 1
+const MIGRATIONS: [fn(Value) -> Result<Value, String>; VERSION as usize] = [v0_to_v1];
+
+// version 0 was the rules.json the app always wrote, a bare list of rules with only a name,
+// filters and an action, and layover filters took a single airport code
+fn v0_to_v1(mut rules: Value) -> Result<Value, String> {
+    fn layover_lists(filter: &mut Value) {
+        let Some(filter) = filter.as_object_mut() else {
+            return;
+        };
+        for (name, value) in filter.iter_mut() {
+            match (name.as_str(), value) {
+                ("IncludeLayover" | "ExcludeLayover", code @ Value::String(_)) => {
+                    *code = json!([code.take()])
+                }
+                ("All" | "Any" | "Not", Value::Array(children)) => {
+                    children.iter_mut().for_each(layover_lists)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for rule in rules.as_array_mut().ok_or("expected a list of rules")? {
+        let rule = rule.as_object_mut().ok_or("expected a rule object")?;
+        // the strongest action won, Ignore over Pickup over Alert over Nothing,
+        // ranking by the action keeps these rules deciding the same way
+        let action: BotAction = rule
+            .get("action")
+            .and_then(|a| serde_json::from_value(a.clone()).ok())
+            .ok_or("expected a rule action")?;
+        rule.insert("priority".to_owned(), json!(action as u8));
+        rule.insert("enabled".to_owned(), json!(true));
+        rule.insert("notes".to_owned(), json!(""));
+        rule.insert("points".to_owned(), json!(0));
+        rule.insert("per_hour".to_owned(), json!(null));
+        if let Some(filters) = rule.get_mut("filters").and_then(Value::as_array_mut) {
+            filters.iter_mut().for_each(layover_lists);
+        }
+    }
+    Ok(json!({ "version": 1, "mode": "Priority", "ranked": false, "rules": rules }))
+}
+
+fn version(file: &Value) -> Result<u64, LoadRulesError> {
+    match file {
+        Value::Array(_) => Ok(0),
+        Value::Object(o) => match o.get("version") {
+            None => Err(LoadRulesError::Migrate(0, "no version".to_owned())),
+            Some(v) => v.as_u64().ok_or(LoadRulesError::Migrate(
+                0,
+                format!("version {} is not a number", v),
+            )),
+        },
+        _ => Err(LoadRulesError::Migrate(0, "not a rules file".to_owned())),
+    }
+}
+
+pub fn parse(data: &str) -> Result<RuleSet, LoadRulesError> {
+    let mut file: Value = serde_json::from_str(data).map_err(LoadRulesError::Parse)?;
+    let from = version(&file)?;
+    if from > VERSION {
+        return Err(LoadRulesError::Newer(from));
+    }
+    for v in from..VERSION {
+        file = MIGRATIONS[v as usize](file).map_err(|e| LoadRulesError::Migrate(v, e))?;
+    }
+
+    if let Some(o) = file.as_object_mut() {
+        o.remove("version");
+    }
+    serde_json::from_value(file).map_err(LoadRulesError::Parse)
+}
+
+pub fn to_json(set: &RuleSet) -> String {
+    let mut file = serde_json::to_value(set).unwrap_or_default();
+    if let Some(o) = file.as_object_mut() {
+        o.insert("version".to_owned(), json!(VERSION));
+    }
+    serde_json::to_string_pretty(&file).unwrap_or_default()
+}
+
+pub fn load_rules(path: &str) -> Result<RuleSet, LoadRulesError> {
+    parse(&fs::read_to_string(path).map_err(LoadRulesError::Io)?)
+}
+
+pub fn save_rules(path: &str, set: &RuleSet) -> Result<(), std::io::Error> {
+    fs::write(path, to_json(set))
+}
+
+#[derive(Debug)]
+pub enum LoadRulesError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    // saved by a newer version of the app
+    Newer(u64),
+    // the version the migration started from
+    Migrate(u64, String),
+}
+
+impl Display for LoadRulesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadRulesError::Io(e) => write!(f, "could not read rules: {}", e),
+            LoadRulesError::Parse(e) => write!(f, "could not parse rules: {}", e),
+            LoadRulesError::Newer(v) => write!(
+                f,
+                "rules file is version {}, this version of the app reads up to {}, update to load it",
+                v, VERSION
+            ),
+            LoadRulesError::Migrate(v, e) => {
+                write!(f, "could not upgrade version {} rules file: {}", v, e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{EvalContext, Filter, MatchMode, decide, test_support::parse_trip};
+
+    #[test]
+    fn migrates_bare_list() {
+        let set =
+            parse(r#"[{"name":"dublin","filters":[{"Not":[{"IncludeLayover":"DUB"}]}],"action":"Pickup"}]"#)
+                .unwrap();
+
+        assert_eq!(set.mode, MatchMode::Priority);
+        assert_eq!(
+            set.rules[0].filters,
+            [Filter::Not(vec![Filter::IncludeLayover(vec![
+                "DUB".to_owned()
+            ])])]
+        );
+        assert_eq!(set.rules[0].action, BotAction::Pickup);
+        assert_eq!(set.rules[0].priority, 3);
+        assert!(set.rules[0].enabled);
+        assert_eq!(set.rules[0].notes, "");
+        assert_eq!(set.rules[0].points, 0);
+        assert_eq!(set.rules[0].per_hour, None);
+        assert!(!set.ranked);
+    }
+
+    #[test]
+    fn keeps_ignore_over_pickup() {
+        // the broad pickup comes first, but ignore always won in files this old
+        let set = parse(
+            r#"[{"name":"anything","filters":[],"action":"Pickup"},
+                {"name":"not dublin","filters":[{"IncludeLayover":"DUB"}],"action":"Ignore"}]"#,
+        )
+        .unwrap();
+        let mut trip = parse_trip("W1030 05JAN 2 0600 0700 1200 0500 0500 DUB");
+        let ctx = EvalContext::default();
+
+        assert_eq!(decide(&set, &trip, &ctx).0, BotAction::Ignore);
+        trip.layovers = vec!["EDI".to_owned()];
+        assert_eq!(decide(&set, &trip, &ctx).0, BotAction::Pickup);
+    }
+
+    #[test]
+    fn migration_keeps_decisions() {
+        let v0 = r#"[
+            {"name":"alert long","filters":[{"NumDays":["GtEq",3]}],"action":"Alert"},
+            {"name":"pickup premium","filters":["IsPrem"],"action":"Pickup"},
+            {"name":"nothing short","filters":[{"NumDays":["Eq",1]}],"action":"Nothing"},
+            {"name":"ignore dublin","filters":[{"IncludeLayover":"DUB"}],"action":"Ignore"},
+            {"name":"pickup any","filters":[],"action":"Pickup"}
+        ]"#;
+        let set = parse(v0).unwrap();
+        let ctx = EvalContext::default();
+
+        for days in 1..=4 {
+            for premium in [false, true] {
+                for layover in ["DUB", "EDI"] {
+                    let trip = parse_trip(&format!(
+                        "W1030 05JAN {} 0600 0700 1200 0500 0500 {}{}",
+                        days,
+                        layover,
+                        if premium { " X" } else { "" }
+                    ));
+                    // how version 0 decided, the strongest action of any matching rule
+                    let before = set
+                        .rules
+                        .iter()
+                        .filter(|r| r.eval(&trip, &ctx))
+                        .map(|r| r.action)
+                        .max_by_key(|a| *a as u8)
+                        .unwrap_or(BotAction::Nothing);
+                    assert_eq!(decide(&set, &trip, &ctx).0, before, "{:?}", trip);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_current_version() {
+        let mut set =
+            parse(r#"[{"name":"premium","filters":["IsPrem"],"action":"Alert"}]"#).unwrap();
+        set.rules[0].enabled = false;
+        let js = to_json(&set);
+
+        assert!(js.contains(&format!("\"version\": {}", VERSION)));
+        assert_eq!(parse(&js).unwrap(), set);
+    }
+
+    #[test]
+    fn reports_bad_files() {
+        assert!(matches!(
+            parse(r#"{"version":99,"mode":"Priority","rules":[]}"#),
+            Err(LoadRulesError::Newer(99))
+        ));
+        assert!(matches!(
+            parse(r#"[{"name":"x","filters":[{"NoSuchFilter":1}],"action":"Alert"}]"#),
+            Err(LoadRulesError::Parse(_))
+        ));
+        assert!(matches!(parse("42"), Err(LoadRulesError::Migrate(0, _))));
+        assert!(matches!(
+            parse(r#"{"mode":"Priority","rules":[]}"#),
+            Err(LoadRulesError::Migrate(0, _))
+        ));
+    }
+}
@@ -0,0 +1,422 @@
+use crate::bot::{Date, Field, Time, Trip};
+use chrono::{Local, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, fs, path::Path};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ItemKind {
+    Trip,
+    Off,
+}
+
+// something already on our schedule, the end is exclusive
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledItem {
+    pub kind: ItemKind,
+    pub name: String,
+    pub start: Date,
+    pub start_time: Time,
+    pub end: Date,
+    pub end_time: Time,
+}
+
+impl ScheduledItem {
+    fn start_minute(&self) -> i64 {
+        minute(self.start, self.start_time)
+    }
+
+    fn end_minute(&self) -> i64 {
+        minute(self.end, self.end_time)
+    }
+}
+
+impl Display for ScheduledItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match (self.kind, self.name.is_empty()) {
+            (ItemKind::Off, true) => "day off",
+            _ => &self.name,
+        };
+        write!(
+            f,
+            "{} ({} {} to {} {})",
+            name, self.start, self.start_time, self.end, self.end_time
+        )
+    }
+}
+
+// minutes since the start of the calendar, so items and trips on different days compare
+fn minute(date: Date, time: Time) -> i64 {
+    date.day_number() * 24 * 60 + time.minutes() as i64
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub items: Vec<ScheduledItem>,
+}
+
+impl Schedule {
+    // the first item the trip overlaps, or that leaves less than `rest` between it and the trip,
+    // rest only applies between trips, days off just can't be flown into
+    pub fn conflict(&self, trip: &Trip, rest: Time) -> Option<&ScheduledItem> {
+        let day = trip.date.day_number() * 24 * 60;
+        let start = day + trip.offset(Field::Report) as i64;
+        let end = day + trip.offset(Field::Arrive) as i64;
+
+        self.items.iter().find(|item| {
+            let rest = match item.kind {
+                ItemKind::Trip => rest.minutes() as i64,
+                ItemKind::Off => 0,
+            };
+            item.start_minute() < end + rest && start < item.end_minute() + rest
+        })
+    }
+
+    // a trip being picked up, from its report to its final arrival
+    pub fn add_trip(&mut self, trip: &Trip) {
+        const DAY: u32 = 24 * 60;
+
+        let arrive = trip.offset(Field::Arrive);
+        self.items.push(ScheduledItem {
+            kind: ItemKind::Trip,
+            name: trip.id.clone(),
+            start: trip.date,
+            start_time: trip.report,
+            end: trip.date.add_days((arrive / DAY) as i64),
+            end_time: Time::from_minutes(arrive % DAY),
+        });
+    }
+
+    pub fn load(path: &str) -> Result<Self, ScheduleError> {
+        let data = fs::read_to_string(path).map_err(ScheduleError::Io)?;
+        serde_json::from_str(&data).map_err(|e| ScheduleError::Parse(0, e.to_string()))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), ScheduleError> {
+        let js = serde_json::to_string_pretty(self).unwrap_or_default();
+        fs::write(path, js).map_err(ScheduleError::Io)
+    }
+
+    // picks the format from the extension, .ics or .csv
+    pub fn import(path: &str) -> Result<Self, ScheduleError> {
+        let data = fs::read_to_string(path).map_err(ScheduleError::Io)?;
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("ics") => Schedule::from_ics(&data),
+            Some(e) if e.eq_ignore_ascii_case("csv") => Schedule::from_csv(&data),
+            _ => Err(ScheduleError::UnknownFormat),
+        }
+    }
+
+    // one item per line: kind,name,start,end
+    // kind is "trip" or "off", start and end are "YYYY-MM-DD" or "YYYY-MM-DD HH:MM"
+    // a date without a time covers the whole day, so "off,,2026-01-10,2026-01-11" is two days off
+    pub fn from_csv(text: &str) -> Result<Self, ScheduleError> {
+        let mut items = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || (i == 0 && line.to_lowercase().starts_with("kind")) {
+                continue;
+            }
+            let bad = |what: &str| ScheduleError::Parse(i + 1, format!("{} in \"{}\"", what, line));
+
+            let cols: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [kind, name, start, end] = cols[..] else {
+                return Err(bad("expected 4 columns"));
+            };
+            let kind = match kind.to_lowercase().as_str() {
+                "trip" => ItemKind::Trip,
+                "off" => ItemKind::Off,
+                _ => return Err(bad("unknown kind")),
+            };
+            let (start, start_time) = csv_datetime(start, false).ok_or_else(|| bad("bad start"))?;
+            let (end, end_time) = csv_datetime(end, true).ok_or_else(|| bad("bad end"))?;
+
+            items.push(ScheduledItem {
+                kind,
+                name: name.to_owned(),
+                start,
+                start_time,
+                end,
+                end_time,
+            });
+        }
+        Ok(Schedule { items })
+    }
+
+    // reads the VEVENTs, events with a time are trips and all day events are days off
+    pub fn from_ics(text: &str) -> Result<Self, ScheduleError> {
+        Schedule::from_ics_in(text, &Local)
+    }
+
+    // UTC times are moved into `local`, the zone the board shows times in
+    fn from_ics_in(text: &str, local: &impl TimeZone) -> Result<Self, ScheduleError> {
+        let mut items = Vec::new();
+        let mut event: Option<(String, Option<IcsTime>, Option<IcsTime>)> = None;
+
+        // long lines are folded onto the next line starting with a space
+        let mut lines: Vec<(usize, String)> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+                (Some(rest), Some((_, last))) => last.push_str(rest),
+                _ => lines.push((i + 1, line.trim_end().to_owned())),
+            }
+        }
+
+        for (number, line) in lines {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let (name, params) = key.split_once(';').unwrap_or((key, ""));
+            let bad = || ScheduleError::Parse(number, format!("bad {} \"{}\"", name, value));
+
+            match (name, &mut event) {
+                // named zones would need the tz database to convert
+                ("DTSTART" | "DTEND", Some(_)) if params.contains("TZID=") => {
+                    return Err(ScheduleError::Parse(
+                        number,
+                        format!(
+                            "{} has a time zone, only UTC and local times can be imported",
+                            name
+                        ),
+                    ));
+                }
+                ("BEGIN", None) if value == "VEVENT" => event = Some((String::new(), None, None)),
+                ("SUMMARY", Some(e)) => e.0 = value.to_owned(),
+                ("DTSTART", Some(e)) => e.1 = Some(ics_time(value, params, local).ok_or_else(bad)?),
+                ("DTEND", Some(e)) => e.2 = Some(ics_time(value, params, local).ok_or_else(bad)?),
+                ("END", Some(_)) if value == "VEVENT" => {
+                    let Some((summary, Some(start), end)) = event.take() else {
+                        return Err(ScheduleError::Parse(number, "event without DTSTART".into()));
+                    };
+                    // an all day event without an end is one day long
+                    let end = end.unwrap_or(match start {
+                        IcsTime::Day(d) => IcsTime::Day(d.add_days(1)),
+                        t => t,
+                    });
+                    // days off come as all day events, trips always have times
+                    let kind = match start {
+                        IcsTime::Day(_) => ItemKind::Off,
+                        IcsTime::At(..) => ItemKind::Trip,
+                    };
+                    let (start, start_time) = start.into();
+                    let (end, end_time) = end.into();
+                    items.push(ScheduledItem {
+                        kind,
+                        name: summary,
+                        start,
+                        start_time,
+                        end,
+                        end_time,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(Schedule { items })
+    }
+}
+
+fn csv_datetime(s: &str, end: bool) -> Option<(Date, Time)> {
+    let (date, time) = s.split_once(' ').unwrap_or((s, ""));
+    let date: Date = date.parse().ok()?;
+    match time.trim() {
+        // the end date is included, so it runs until midnight
+        "" if end => Some((date.add_days(1), Time::default())),
+        "" => Some((date, Time::default())),
+        time => Some((date, time.parse().ok().filter(Time::is_clock)?)),
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum IcsTime {
+    Day(Date),
+    At(Date, Time),
+}
+
+impl From<IcsTime> for (Date, Time) {
+    fn from(value: IcsTime) -> Self {
+        match value {
+            IcsTime::Day(d) => (d, Time::default()),
+            IcsTime::At(d, t) => (d, t),
+        }
+    }
+}
+
+// "20260105" for all day events, "20260105T0600" with optional seconds otherwise,
+// times ending in Z are UTC and the rest are already local
+fn ics_time(value: &str, params: &str, local: &impl TimeZone) -> Option<IcsTime> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        return match ics_time(utc, params, local)? {
+            IcsTime::At(date, time) => {
+                let utc =
+                    date.to_naive()?
+                        .and_hms_opt(time.hours as u32, time.minutes as u32, 0)?;
+                let local = Utc.from_utc_datetime(&utc).with_timezone(local);
+                Some(IcsTime::At(
+                    Date::from_naive(local.date_naive()),
+                    Time::from_minutes(local.hour() * 60 + local.minute()),
+                ))
+            }
+            day => Some(day),
+        };
+    }
+    if !value.is_ascii() || value.len() < 8 {
+        return None;
+    }
+    let date: Date = format!("{}-{}-{}", &value[0..4], &value[4..6], &value[6..8])
+        .parse()
+        .ok()?;
+
+    match value[8..].strip_prefix('T') {
+        None if value.len() == 8 || params.contains("VALUE=DATE") => Some(IcsTime::Day(date)),
+        Some(t) if t.len() >= 4 => Some(IcsTime::At(
+            date,
+            t[..4].parse().ok().filter(Time::is_clock)?,
+        )),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub enum ScheduleError {
+    Io(std::io::Error),
+    // line number, 0 for schedule.json
+    Parse(usize, String),
+    UnknownFormat,
+}
+
+impl Display for ScheduleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleError::Io(e) => write!(f, "could not read schedule: {}", e),
+            ScheduleError::Parse(0, e) => write!(f, "could not parse schedule: {}", e),
+            ScheduleError::Parse(line, e) => write!(f, "line {}: {}", line, e),
+            ScheduleError::UnknownFormat => write!(f, "schedule must be a .ics or .csv file"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{
+        BotAction, EvalContext, decide,
+        test_support::{parse_trip as trip, pickup_everything},
+    };
+    use chrono::FixedOffset;
+
+    #[test]
+    fn imports_csv() {
+        let schedule = Schedule::from_csv(
+            "kind,name,start,end\n\
+             trip,W1030,2026-01-05 06:00,2026-01-07 18:30\n\
+             off,,2026-01-10,2026-01-11\n",
+        )
+        .unwrap();
+
+        assert_eq!(schedule.items.len(), 2);
+        assert_eq!(
+            schedule.items[1].to_string(),
+            "day off (JAN 10, 2026 00:00 to JAN 12, 2026 00:00)"
+        );
+        assert!(Schedule::from_csv("trip,W1030,2026-01-05 25:00,2026-01-07").is_err());
+    }
+
+    #[test]
+    fn imports_ics() {
+        // five hours behind UTC
+        let local = FixedOffset::west_opt(5 * 3600).unwrap();
+        let schedule = Schedule::from_ics_in(
+            "BEGIN:VCALENDAR\r\n\
+             BEGIN:VEVENT\r\n\
+             SUMMARY:W1030 DUB\r\n \
+             EDI\r\n\
+             DTSTART:20260105T030000Z\r\n\
+             DTEND:20260107T183000Z\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             SUMMARY:Off\r\n\
+             DTSTART;VALUE=DATE:20260110\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             SUMMARY:W2040\r\n\
+             DTSTART:20260120T0600\r\n\
+             DTEND:20260120T1800\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             SUMMARY:W3050\r\n\
+             DTSTART:20260125T0000\r\n\
+             DTEND:20260126T0000\r\n\
+             END:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+            &local,
+        )
+        .unwrap();
+
+        // UTC times move to local, back across midnight for the start
+        assert_eq!(
+            schedule.items[0].to_string(),
+            "W1030 DUBEDI (JAN 04, 2026 22:00 to JAN 07, 2026 13:30)"
+        );
+        assert_eq!(schedule.items[1].kind, ItemKind::Off);
+        assert_eq!(schedule.items[1].end.day, 11);
+        // times without Z are already local
+        assert_eq!(
+            schedule.items[2].to_string(),
+            "W2040 (JAN 20, 2026 06:00 to JAN 20, 2026 18:00)"
+        );
+        // a trip from midnight to midnight still has times
+        assert_eq!(schedule.items[3].kind, ItemKind::Trip);
+
+        assert!(matches!(
+            Schedule::from_ics_in(
+                "BEGIN:VEVENT\nDTSTART;TZID=Europe/Dublin:20260105T060000\nEND:VEVENT\n",
+                &local
+            ),
+            Err(ScheduleError::Parse(2, _))
+        ));
+    }
+
+    #[test]
+    fn finds_conflicts() {
+        let schedule = Schedule::from_csv(
+            "trip,W1030,2026-01-05 06:00,2026-01-07 18:30\n\
+             off,,2026-01-10,2026-01-10\n",
+        )
+        .unwrap();
+        let rest = Time {
+            hours: 10,
+            minutes: 0,
+        };
+
+        // overlaps the end of W1030
+        let t = trip("A1 07JAN 1 1200 1300 1800 0500 0500");
+        assert_eq!(
+            schedule.conflict(&t, Time::default()).unwrap().name,
+            "W1030"
+        );
+        // reports 11h30 after W1030, fine with 10h rest but not 12h
+        let t = trip("A2 08JAN 1 0600 0700 1200 0500 0500");
+        assert_eq!(schedule.conflict(&t, rest), None);
+        assert!(schedule.conflict(&t, Time::from_minutes(12 * 60)).is_some());
+        // overnight into the day off
+        let t = trip("A3 09JAN 1 2000 2100 0130 0430 0430");
+        assert_eq!(schedule.conflict(&t, rest).unwrap().kind, ItemKind::Off);
+        // lands the evening before the day off, rest doesn't apply
+        let t = trip("A4 09JAN 1 0600 0700 2200 1500 1500");
+        assert_eq!(schedule.conflict(&t, rest), None);
+    }
+
+    #[test]
+    fn never_picks_up_conflicts() {
+        let ctx = EvalContext {
+            schedule: Schedule::from_csv("off,,2026-01-10,2026-01-10").unwrap(),
+            ..EvalContext::default()
+        };
+        let rules = pickup_everything();
+
+        let t = trip("A1 09JAN 2 0600 0700 1200 0500 0500");
+        assert_eq!(decide(&rules, &t, &ctx).0, BotAction::Alert);
+        let t = trip("A2 11JAN 2 0600 0700 1200 0500 0500");
+        assert_eq!(decide(&rules, &t, &ctx).0, BotAction::Pickup);
+    }
+}
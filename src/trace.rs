@@ -0,0 +1,150 @@
+use crate::bot::{BotAction, EvalContext, Filter, Rule, RuleSet, Trip, decide, score};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FilterTrace {
+    pub filter: String,
+    pub passed: bool,
+    // the trip values that were compared, empty for groups
+    pub actual: String,
+    pub children: Vec<FilterTrace>,
+}
+
+impl FilterTrace {
+    pub fn new(filter: &Filter, trip: &Trip, ctx: &EvalContext) -> Self {
+        FilterTrace {
+            filter: filter.as_string(),
+            passed: filter.eval(trip, ctx),
+            actual: filter.actual(trip, ctx),
+            children: filter
+                .children()
+                .map(|filters| {
+                    filters
+                        .iter()
+                        .map(|f| FilterTrace::new(f, trip, ctx))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RuleTrace {
+    pub rule: String,
+    pub action: BotAction,
+    pub enabled: bool,
+    pub passed: bool,
+    // added to the trip's score, 0 unless the rule is enabled and passed
+    pub points: i32,
+    pub filters: Vec<FilterTrace>,
+}
+
+impl RuleTrace {
+    pub fn new(rule: &Rule, trip: &Trip, ctx: &EvalContext) -> Self {
+        let passed = rule.eval(trip, ctx);
+        RuleTrace {
+            rule: rule.name.clone(),
+            action: rule.action,
+            enabled: rule.enabled,
+            passed,
+            points: if rule.enabled && passed {
+                rule.points_for(trip)
+            } else {
+                0
+            },
+            filters: rule
+                .filters
+                .iter()
+                .map(|f| FilterTrace::new(f, trip, ctx))
+                .collect(),
+        }
+    }
+}
+
+// every rule and filter evaluated against one trip, not just the first failure
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TripTrace {
+    pub trip: String,
+    pub action: BotAction,
+    pub decided_by: Option<String>,
+    // only for ranked rule sets
+    pub score: Option<i32>,
+    pub rules: Vec<RuleTrace>,
+}
+
+impl TripTrace {
+    pub fn new(set: &RuleSet, trip: &Trip, ctx: &EvalContext) -> Self {
+        let (action, rule) = decide(set, trip, ctx);
+        TripTrace {
+            trip: trip.id.clone(),
+            action,
+            decided_by: rule.map(|r| r.name.clone()),
+            score: set.ranked.then(|| score(set, trip, ctx)),
+            rules: set
+                .rules
+                .iter()
+                .map(|r| RuleTrace::new(r, trip, ctx))
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bot::{Date, MatchMode, Op},
+        opentime,
+    };
+
+    #[test]
+    fn traces_every_filter() {
+        let page = opentime::parse(
+            "W1102 06JAN 4 1315 1415 0905 2210 2330 LHR DUB\n",
+            Date::default(),
+        );
+        let rules = RuleSet {
+            mode: MatchMode::Priority,
+            ranked: false,
+            rules: vec![Rule {
+                name: "short dublin".to_owned(),
+                filters: vec![
+                    Filter::NumDays(Op::LtEq, 2),
+                    Filter::Any(vec![
+                        Filter::IncludeLayover(vec!["DUB".to_owned()]),
+                        Filter::IncludeLayover(vec!["EDI".to_owned()]),
+                    ]),
+                ],
+                action: BotAction::Pickup,
+                ..Default::default()
+            }],
+        };
+        let trace = TripTrace::new(&rules, &page.trips[0], &EvalContext::default());
+
+        assert_eq!(trace.action, BotAction::Nothing);
+        assert_eq!(trace.decided_by, None);
+
+        let rule = &trace.rules[0];
+        assert!(!rule.passed);
+        assert_eq!(
+            rule.filters
+                .iter()
+                .map(|f| (f.passed, f.actual.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(false, "Days 4"), (true, "")]
+        );
+        assert_eq!(
+            rule.filters[1]
+                .children
+                .iter()
+                .map(|f| (f.passed, f.actual.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(true, "Layovers [LHR DUB]"), (false, "Layovers [LHR DUB]")]
+        );
+    }
+}